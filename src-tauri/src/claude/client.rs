@@ -1,66 +1,427 @@
-use reqwest::Client;
+use std::sync::Mutex;
+use std::time::Duration;
 
-use super::types::{Message, MessageRequest, MessageResponse};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Proxy, RequestBuilder, Response};
 
-const API_URL: &str = "https://api.anthropic.com/v1/messages";
+use super::error::ClaudeError;
+use super::rate_limit::TokenBudget;
+use super::retry::{RetryHint, RetryPolicy};
+use super::stream::SseDecoder;
+use super::types::{
+    BatchRequest, BatchResult, ContentDelta, CreateBatchRequest, Message, MessageBatch,
+    MessageRequest, MessageResponse, StreamEvent, SystemBlock, Tool, ToolChoice, Usage,
+};
+use crate::types::{ApiSettings, TokenUsage};
+
+const MESSAGES_PATH: &str = "/v1/messages";
+const BATCHES_PATH: &str = "/v1/messages/batches";
+const MODELS_PATH: &str = "/v1/models";
 const API_VERSION: &str = "2023-06-01";
 
 pub struct ClaudeClient {
     client: Client,
+    base_url: String,
     api_key: String,
     model: String,
+    retry: RetryPolicy,
+    budget: TokenBudget,
+    usage: Mutex<TokenUsage>,
+}
+
+/// Rough token count of a request before it is sent: ~4 characters per input
+/// token (prompt and tool schemas) plus the full output allowance.
+fn estimate_tokens(request: &MessageRequest) -> u32 {
+    let chars = request
+        .system
+        .iter()
+        .map(|b| b.text.len())
+        .sum::<usize>()
+        + request
+            .messages
+            .iter()
+            .map(|m| m.content.len())
+            .sum::<usize>()
+        + request
+            .tools
+            .iter()
+            .map(|t| t.description.len() + t.input_schema.to_string().len())
+            .sum::<usize>();
+    (chars / 4) as u32 + request.max_tokens
+}
+
+/// Tokens a response counts against the budget: uncached input, cache writes
+/// and output. Cache reads are left out, since they don't count towards the
+/// API's input token rate limit.
+fn budget_tokens(usage: &Usage) -> u32 {
+    let Usage {
+        input_tokens,
+        output_tokens,
+        cache_creation_input_tokens,
+        cache_read_input_tokens: _,
+    } = usage;
+    input_tokens + cache_creation_input_tokens + output_tokens
 }
 
 impl ClaudeClient {
-    pub fn new(api_key: String, model: String) -> Self {
-        Self {
-            client: Client::new(),
+    /// A client for the API at `settings.base_url`. Fails on an invalid extra
+    /// header or proxy URL.
+    pub fn new(api_key: String, model: String, settings: &ApiSettings) -> Result<Self, String> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &settings.headers {
+            let header = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("Invalid header name {:?}: {}", name, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| format!("Invalid value of header {}: {}", name, e))?;
+            headers.insert(header, value);
+        }
+
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(settings.request_timeout_secs))
+            .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+            .default_headers(headers);
+        if let Some(proxy) = settings.proxy.as_deref().filter(|p| !p.is_empty()) {
+            let proxy =
+                Proxy::all(proxy).map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            base_url: settings.base_url.trim_end_matches('/').to_string(),
             api_key,
             model,
-        }
+            retry: RetryPolicy::from_env(),
+            budget: TokenBudget::from_env(),
+            usage: Mutex::new(TokenUsage::default()),
+        })
     }
 
-    pub async fn send_message(
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Token usage of every request this client completed so far.
+    pub fn usage(&self) -> TokenUsage {
+        self.usage.lock().expect("usage lock poisoned").clone()
+    }
+
+    /// Add a response's usage to the client's totals.
+    fn record_usage(&self, usage: &Usage) {
+        let mut total = self.usage.lock().expect("usage lock poisoned");
+        total.input_tokens += u64::from(usage.input_tokens);
+        total.output_tokens += u64::from(usage.output_tokens);
+        total.cache_creation_tokens += u64::from(usage.cache_creation_input_tokens);
+        total.cache_read_tokens += u64::from(usage.cache_read_input_tokens);
+    }
+
+    fn request(
         &self,
-        system: &str,
+        system: &[SystemBlock],
         user_message: &str,
         max_tokens: u32,
-    ) -> Result<String, String> {
-        let request = MessageRequest {
+    ) -> MessageRequest {
+        MessageRequest {
             model: self.model.clone(),
             max_tokens,
-            system: system.to_string(),
+            system: system.to_vec(),
             messages: vec![Message {
                 role: "user".to_string(),
                 content: user_message.to_string(),
             }],
-        };
+            tools: vec![],
+            tool_choice: None,
+            stream: false,
+        }
+    }
+
+    /// A single-turn request that must be answered by calling `tool`.
+    pub fn tool_request(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        tool: Tool,
+    ) -> MessageRequest {
+        let mut request = self.request(system, user_message, max_tokens);
+        request.tool_choice = Some(ToolChoice::Tool {
+            name: tool.name.clone(),
+        });
+        request.tools = vec![tool];
+        request
+    }
+
+    /// Send a single-turn message that must be answered by calling `tool`, and
+    /// return the tool input the model produced.
+    pub async fn call_tool(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        tool: Tool,
+    ) -> Result<serde_json::Value, ClaudeError> {
+        let tool_name = tool.name.clone();
+        let request = self.tool_request(system, user_message, max_tokens, tool);
+        let response = self.send(&request).await?;
+        forced_tool_input(&response, &tool_name)
+    }
+
+    /// Submit requests to the Message Batches API, each under its `custom_id`.
+    pub async fn create_batch(
+        &self,
+        requests: Vec<BatchRequest>,
+    ) -> Result<MessageBatch, ClaudeError> {
+        let body = CreateBatchRequest { requests };
+        let url = self.url(BATCHES_PATH);
+        self.execute_with_retries(self.authorized(self.client.post(url)).json(&body))
+            .await?
+            .json()
+            .await
+            .map_err(|e| ClaudeError::Parse(e.to_string()))
+    }
+
+    /// Current status of a message batch.
+    pub async fn get_batch(&self, batch_id: &str) -> Result<MessageBatch, ClaudeError> {
+        let url = format!("{}/{}", self.url(BATCHES_PATH), batch_id);
+        self.execute_with_retries(self.authorized(self.client.get(url)))
+            .await?
+            .json()
+            .await
+            .map_err(|e| ClaudeError::Parse(e.to_string()))
+    }
 
-        let response = self
-            .client
-            .post(API_URL)
+    /// Check the API key with a request that uses no tokens: listing one
+    /// model. Not retried, so a bad key is reported right away.
+    pub async fn check_key(&self) -> Result<(), ClaudeError> {
+        let url = format!("{}?limit=1", self.url(MODELS_PATH));
+        self.execute(self.authorized(self.client.get(url)))
+            .await
+            .map(|_| ())
+            .map_err(|(error, _)| error)
+    }
+
+    /// Results of an ended batch, one per request, in no particular order.
+    pub async fn batch_results(
+        &self,
+        batch: &MessageBatch,
+    ) -> Result<Vec<BatchResult>, ClaudeError> {
+        let url = batch
+            .results_url
+            .clone()
+            .unwrap_or_else(|| format!("{}/{}/results", self.url(BATCHES_PATH), batch.id));
+        let body = self
+            .execute_with_retries(self.authorized(self.client.get(url)))
+            .await?
+            .text()
+            .await
+            .map_err(ClaudeError::from)?;
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|e| ClaudeError::Parse(format!("Invalid batch result: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Stream a single-turn reply, passing each text delta to `on_text` as it
+    /// arrives, and return the full text.
+    ///
+    /// Failures before the stream starts are retried like `send`. Once text has
+    /// been delivered a failure is returned as is, since a retry would repeat it.
+    pub async fn stream_message(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        on_text: impl FnMut(&str),
+    ) -> Result<String, ClaudeError> {
+        let mut request = self.request(system, user_message, max_tokens);
+        request.stream = true;
+
+        let estimate = estimate_tokens(&request);
+        let request = &request;
+        let (response, reservation) = self
+            .retry
+            .run(|| async move {
+                let reservation = self.budget.acquire(estimate).await;
+                match self.post(request).await {
+                    Ok(response) => Ok((response, reservation)),
+                    Err(failure) => {
+                        self.budget.settle(reservation, 0);
+                        Err(failure)
+                    }
+                }
+            })
+            .await?;
+
+        let mut usage: Option<Usage> = None;
+        let result = read_stream(response, &mut usage, on_text).await;
+        // Settled however the stream ended: a reply that broke off still used
+        // the tokens reported so far
+        self.budget.settle(reservation, usage.as_ref().map_or(0, budget_tokens));
+        if let Some(usage) = &usage {
+            self.record_usage(usage);
+        }
+        result
+    }
+
+    /// Send a request, retrying rate limits, overloads, server and network
+    /// errors with exponential backoff. Each attempt waits for room in the
+    /// client's tokens-per-minute budget.
+    async fn send(&self, request: &MessageRequest) -> Result<MessageResponse, ClaudeError> {
+        let estimate = estimate_tokens(request);
+        self.retry
+            .run(|| async move {
+                let reservation = self.budget.acquire(estimate).await;
+                match self.try_send(request).await {
+                    Ok(response) => {
+                        self.budget.settle(reservation, budget_tokens(&response.usage));
+                        self.record_usage(&response.usage);
+                        Ok(response)
+                    }
+                    Err(failure) => {
+                        // A failed attempt reports no usage; its estimate would
+                        // otherwise hold up the window for a minute
+                        self.budget.settle(reservation, 0);
+                        Err(failure)
+                    }
+                }
+            })
+            .await
+    }
+
+    /// One request. On failure, also returns the server's retry guidance.
+    async fn try_send(
+        &self,
+        request: &MessageRequest,
+    ) -> Result<MessageResponse, (ClaudeError, RetryHint)> {
+        self.post(request)
+            .await?
+            .json()
+            .await
+            .map_err(|e| (ClaudeError::Parse(e.to_string()), RetryHint::default()))
+    }
+
+    /// Post a request and check its status, leaving the body unread.
+    async fn post(&self, request: &MessageRequest) -> Result<Response, (ClaudeError, RetryHint)> {
+        self.execute(self.authorized(self.client.post(self.url(MESSAGES_PATH))).json(request))
+            .await
+    }
+
+    fn authorized(&self, builder: RequestBuilder) -> RequestBuilder {
+        builder
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", API_VERSION)
             .header("content-type", "application/json")
-            .json(&request)
+    }
+
+    /// Send a request built by `authorized`, retrying like `send` but outside
+    /// the token budget: batch calls don't consume the per-minute rate limits.
+    async fn execute_with_retries(&self, builder: RequestBuilder) -> Result<Response, ClaudeError> {
+        self.retry
+            .run(|| {
+                let attempt = builder
+                    .try_clone()
+                    .expect("JSON request bodies can be cloned");
+                self.execute(attempt)
+            })
+            .await
+    }
+
+    /// Send a request and check its status, leaving the body unread.
+    async fn execute(&self, builder: RequestBuilder) -> Result<Response, (ClaudeError, RetryHint)> {
+        let response = builder
             .send()
             .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .map_err(|e| (ClaudeError::from(e), RetryHint::default()))?;
 
-        if !response.status().is_success() {
-            let status = response.status();
+        let status = response.status();
+        if !status.is_success() {
+            let hint = RetryHint::from_headers(response.headers());
             let body = response.text().await.unwrap_or_default();
-            return Err(format!("API error {}: {}", status, body));
+            let error = ClaudeError::from_status(status.as_u16(), &body, hint.delay);
+            return Err((error, hint));
         }
+        Ok(response)
+    }
+}
 
-        let msg: MessageResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Parse error: {}", e))?;
+/// Read a streamed reply up to `message_stop`, passing text deltas to `on_text`
+/// and keeping the latest reported usage in `usage`.
+async fn read_stream(
+    mut response: Response,
+    usage: &mut Option<Usage>,
+    mut on_text: impl FnMut(&str),
+) -> Result<String, ClaudeError> {
+    let mut decoder = SseDecoder::default();
+    let mut text = String::new();
+    while let Some(chunk) = response.chunk().await.map_err(ClaudeError::from)? {
+        for event in decoder.push(&chunk)? {
+            match event {
+                StreamEvent::MessageStart { message } => {
+                    *usage = Some(message.usage);
+                }
+                StreamEvent::ContentBlockDelta {
+                    delta: ContentDelta::TextDelta { text: delta },
+                } => {
+                    on_text(&delta);
+                    text.push_str(&delta);
+                }
+                StreamEvent::MessageDelta {
+                    delta,
+                    usage: delta_usage,
+                } => {
+                    if let Some(usage) = usage.as_mut() {
+                        usage.output_tokens = delta_usage.output_tokens;
+                    }
+                    if delta.stop_reason.as_deref() == Some("max_tokens") {
+                        log::warn!("Streamed reply was cut off at max_tokens");
+                    }
+                }
+                StreamEvent::MessageStop => {
+                    return if text.is_empty() {
+                        Err(ClaudeError::EmptyResponse)
+                    } else {
+                        Ok(text)
+                    };
+                }
+                StreamEvent::Error { error } => {
+                    return Err(ClaudeError::from_stream_error(
+                        &error.error_type,
+                        error.message,
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+    Err(ClaudeError::Network(
+        "stream ended before message_stop".to_string(),
+    ))
+}
 
-        msg.content
-            .first()
-            .and_then(|block| block.text.clone())
-            .ok_or_else(|| "Empty response".to_string())
+/// Input of the forced tool call in `response`. A call cut off at
+/// `max_tokens` is an error, since its input is incomplete.
+pub fn forced_tool_input(
+    response: &MessageResponse,
+    tool_name: &str,
+) -> Result<serde_json::Value, ClaudeError> {
+    if response.stop_reason.as_deref() == Some("max_tokens") {
+        return Err(ClaudeError::Parse(format!(
+            "Tool call to {} was cut off at max_tokens",
+            tool_name
+        )));
     }
+    response
+        .tool_input(tool_name)
+        .cloned()
+        .ok_or(ClaudeError::EmptyResponse)
 }
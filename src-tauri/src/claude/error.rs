@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// Errors from the Claude API, grouped by how the caller should react.
+#[derive(Debug, Error)]
+pub enum ClaudeError {
+    /// Missing, invalid or unauthorised API key (401/403). Not retried.
+    #[error("Authentication failed ({status}): {message}")]
+    Auth { status: u16, message: String },
+
+    /// 429. Retried after the server-provided delay when there is one.
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    /// 529 — the API is temporarily overloaded. Retried.
+    #[error("API overloaded: {message}")]
+    Overloaded { message: String },
+
+    /// 400/404/413/422 — the request itself is wrong. Not retried.
+    #[error("Invalid request ({status}): {message}")]
+    InvalidRequest { status: u16, message: String },
+
+    /// 5xx other than 529. Retried.
+    #[error("API server error ({status}): {message}")]
+    Server { status: u16, message: String },
+
+    /// Connection failures and timeouts. Retried.
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("Failed to parse API response: {0}")]
+    Parse(String),
+
+    #[error("Empty response")]
+    EmptyResponse,
+}
+
+impl ClaudeError {
+    /// Map a non-2xx response to an error variant, using the message from the
+    /// API's `{"error": {"type", "message"}}` body when present.
+    pub fn from_status(status: u16, body: &str, retry_after: Option<Duration>) -> Self {
+        let message = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|v| {
+                v.get("error")
+                    .and_then(|e| e.get("message"))
+                    .and_then(|m| m.as_str())
+                    .map(|m| m.to_string())
+            })
+            .unwrap_or_else(|| body.to_string());
+
+        match status {
+            401 | 403 => Self::Auth { status, message },
+            429 => Self::RateLimited {
+                message,
+                retry_after,
+            },
+            529 => Self::Overloaded { message },
+            500..=599 => Self::Server { status, message },
+            _ => Self::InvalidRequest { status, message },
+        }
+    }
+
+    /// Map an `error` event received mid-stream to an error variant.
+    pub fn from_stream_error(error_type: &str, message: String) -> Self {
+        match error_type {
+            "authentication_error" | "permission_error" => Self::Auth {
+                status: 401,
+                message,
+            },
+            "rate_limit_error" => Self::RateLimited {
+                message,
+                retry_after: None,
+            },
+            "overloaded_error" => Self::Overloaded { message },
+            "api_error" => Self::Server {
+                status: 500,
+                message,
+            },
+            _ => Self::InvalidRequest {
+                status: 400,
+                message,
+            },
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. } | Self::Overloaded { .. } | Self::Server { .. } | Self::Network(_)
+        )
+    }
+}
+
+impl From<reqwest::Error> for ClaudeError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Self::Parse(e.to_string())
+        } else if e.is_timeout() {
+            Self::Network(format!("request timed out: {}", e))
+        } else {
+            Self::Network(e.to_string())
+        }
+    }
+}
+
+impl From<ClaudeError> for String {
+    fn from(e: ClaudeError) -> Self {
+        e.to_string()
+    }
+}
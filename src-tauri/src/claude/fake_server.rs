@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A canned HTTP response of the fake server.
+#[derive(Debug, Clone)]
+pub struct CannedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl CannedResponse {
+    pub fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.to_string(),
+        }
+    }
+
+    /// A Messages API reply that calls `tool_name` with `input`.
+    pub fn tool_use(tool_name: &str, input: Value) -> Self {
+        Self::json(
+            200,
+            json!({
+                "id": "msg_fake",
+                "type": "message",
+                "role": "assistant",
+                "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_fake",
+                    "name": tool_name,
+                    "input": input
+                }],
+                "stop_reason": "tool_use",
+                "usage": { "input_tokens": 100, "output_tokens": 50 }
+            }),
+        )
+    }
+
+    /// An API error body with the given status and error type.
+    pub fn error(status: u16, error_type: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            json!({
+                "type": "error",
+                "error": { "type": error_type, "message": message }
+            }),
+        )
+    }
+
+    /// A 429 asking the client to retry after `retry_after` seconds.
+    pub fn rate_limited(retry_after: u32) -> Self {
+        let mut response = Self::error(429, "rate_limit_error", "Number of requests exceeded");
+        response
+            .headers
+            .push(("retry-after".to_string(), retry_after.to_string()));
+        response
+    }
+}
+
+/// A request the fake server received.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// Responses for requests containing `marker`. Each request takes the next
+/// response; the last one repeats.
+struct Route {
+    marker: String,
+    responses: Vec<CannedResponse>,
+}
+
+#[derive(Default)]
+struct State {
+    routes: Vec<Route>,
+    requests: Vec<RecordedRequest>,
+}
+
+impl State {
+    fn respond(&mut self, request: RecordedRequest) -> CannedResponse {
+        let target = format!("{} {}\n{}", request.method, request.path, request.body);
+        self.requests.push(request);
+        let Some(route) = self.routes.iter_mut().find(|r| target.contains(&r.marker)) else {
+            return CannedResponse::error(404, "not_found_error", "No canned response");
+        };
+        if route.responses.len() > 1 {
+            route.responses.remove(0)
+        } else {
+            route.responses[0].clone()
+        }
+    }
+}
+
+/// A local stand-in for the Messages API, so the client can be tested
+/// offline. Requests are answered by the first route whose marker appears in
+/// `"{method} {path}\n{body}"`, and unmatched requests get a 404. Every
+/// connection is closed after one response.
+pub struct FakeServer {
+    base_url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind fake server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&shared);
+                tokio::spawn(async move {
+                    let _ = serve(stream, state).await;
+                });
+            }
+        });
+        Self { base_url, state }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Answer requests containing `marker` with `responses`, in order.
+    pub fn route(&self, marker: &str, responses: Vec<CannedResponse>) {
+        assert!(!responses.is_empty(), "a route needs a response");
+        self.state.lock().unwrap().routes.push(Route {
+            marker: marker.to_string(),
+            responses,
+        });
+    }
+
+    /// Requests received so far, in arrival order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+/// Read one request, answer it and close the connection.
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<State>>) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let head_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    while buffer.len() < head_end + length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+    let body_end = buffer.len().min(head_end + length);
+    let body = String::from_utf8_lossy(&buffer[head_end..body_end]).to_string();
+
+    let response = state.lock().unwrap().respond(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    });
+    let mut reply = format!(
+        "HTTP/1.1 {} Canned\r\n\
+         content-type: application/json\r\n\
+         content-length: {}\r\n\
+         connection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        reply.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    reply.push_str("\r\n");
+    reply.push_str(&response.body);
+    stream.write_all(reply.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -1,3 +1,10 @@
 pub mod client;
+pub mod error;
+#[cfg(test)]
+pub mod fake_server;
+pub mod pricing;
 pub mod prompts;
+pub mod rate_limit;
+pub mod retry;
+pub mod stream;
 pub mod types;
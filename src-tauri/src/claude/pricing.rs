@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::TokenUsage;
+
+/// Version of the built-in pricing table. Bump whenever prices change so stored
+/// costs can be traced back to the table that produced them.
+pub const PRICING_VERSION: &str = "2025-11";
+
+/// USD prices per million tokens for one model family.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricing {
+    /// Matched against the `model` string of a session, longest prefix wins
+    /// (e.g. `claude-sonnet-4-5` matches `claude-sonnet-4-5-20250929`).
+    pub model: String,
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+    pub cache_write_per_mtok: f64,
+    pub cache_read_per_mtok: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingTable {
+    pub version: String,
+    pub models: Vec<ModelPricing>,
+}
+
+fn price(model: &str, input: f64, output: f64, cache_write: f64, cache_read: f64) -> ModelPricing {
+    ModelPricing {
+        model: model.to_string(),
+        input_per_mtok: input,
+        output_per_mtok: output,
+        cache_write_per_mtok: cache_write,
+        cache_read_per_mtok: cache_read,
+    }
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        Self {
+            version: PRICING_VERSION.to_string(),
+            models: vec![
+                price("claude-opus-4-5", 5.0, 25.0, 6.25, 0.50),
+                price("claude-opus-4-1", 15.0, 75.0, 18.75, 1.50),
+                price("claude-opus-4", 15.0, 75.0, 18.75, 1.50),
+                price("claude-sonnet-4-5", 3.0, 15.0, 3.75, 0.30),
+                price("claude-sonnet-4", 3.0, 15.0, 3.75, 0.30),
+                price("claude-haiku-4-5", 1.0, 5.0, 1.25, 0.10),
+                price("claude-3-7-sonnet", 3.0, 15.0, 3.75, 0.30),
+                price("claude-3-5-sonnet", 3.0, 15.0, 3.75, 0.30),
+                price("claude-3-5-haiku", 0.80, 4.0, 1.0, 0.08),
+                price("claude-3-opus", 15.0, 75.0, 18.75, 1.50),
+                price("claude-3-haiku", 0.25, 1.25, 0.30, 0.03),
+            ],
+        }
+    }
+}
+
+/// User override file: `<config_dir>/codelens/pricing.json`, same shape as `PricingTable`.
+fn override_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("codelens").join("pricing.json"))
+}
+
+impl PricingTable {
+    /// Load the built-in table, merged with the user's override file if present.
+    /// Override entries replace built-in entries with the same `model` key.
+    pub fn load() -> Self {
+        let mut table = Self::default();
+
+        let path = match override_path() {
+            Some(p) if p.exists() => p,
+            _ => return table,
+        };
+
+        let overrides: PricingTable = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))
+        {
+            Ok(t) => t,
+            Err(e) => {
+                log::warn!("Ignoring invalid pricing override {:?}: {}", path, e);
+                return table;
+            }
+        };
+
+        for entry in overrides.models {
+            match table.models.iter_mut().find(|m| m.model == entry.model) {
+                Some(existing) => *existing = entry,
+                None => table.models.push(entry),
+            }
+        }
+        table.version = format!("{}+{}", table.version, overrides.version);
+        table
+    }
+
+    /// Find the pricing entry for a model string (longest matching prefix).
+    pub fn lookup(&self, model: &str) -> Option<&ModelPricing> {
+        self.models
+            .iter()
+            .filter(|m| model.starts_with(&m.model))
+            .max_by_key(|m| m.model.len())
+    }
+
+    /// USD cost of a token usage record. Unknown models cost 0.
+    pub fn cost(&self, model: Option<&str>, usage: &TokenUsage) -> f64 {
+        let pricing = match model.and_then(|m| self.lookup(m)) {
+            Some(p) => p,
+            None => return 0.0,
+        };
+
+        (usage.input_tokens as f64 * pricing.input_per_mtok
+            + usage.output_tokens as f64 * pricing.output_per_mtok
+            + usage.cache_creation_tokens as f64 * pricing.cache_write_per_mtok
+            + usage.cache_read_tokens as f64 * pricing.cache_read_per_mtok)
+            / 1_000_000.0
+    }
+
+    /// USD saved by prompt caching: what the cached tokens would have cost as
+    /// plain input, less what cache writes and reads actually cost. Negative
+    /// while writes outweigh reads.
+    pub fn cache_savings(&self, model: &str, usage: &TokenUsage) -> f64 {
+        let pricing = match self.lookup(model) {
+            Some(p) => p,
+            None => return 0.0,
+        };
+
+        (usage.cache_read_tokens as f64 * (pricing.input_per_mtok - pricing.cache_read_per_mtok)
+            + usage.cache_creation_tokens as f64
+                * (pricing.input_per_mtok - pricing.cache_write_per_mtok))
+            / 1_000_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, output: u64, cache_write: u64, cache_read: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            output_tokens: output,
+            cache_creation_tokens: cache_write,
+            cache_read_tokens: cache_read,
+        }
+    }
+
+    #[test]
+    fn cost_matches_dated_and_aliased_models_and_prices_cache_tokens() {
+        const M: u64 = 1_000_000;
+        let cases: &[(Option<&str>, TokenUsage, f64)] = &[
+            // Dated ids fall back to their family; the longest prefix wins
+            (Some("claude-sonnet-4-5-20250929"), usage(M, 0, 0, 0), 3.0),
+            (Some("claude-opus-4-1-20250805"), usage(0, M, 0, 0), 75.0),
+            (Some("claude-opus-4-20250514"), usage(M, 0, 0, 0), 15.0),
+            (Some("claude-opus-4-5"), usage(M, 0, 0, 0), 5.0),
+            (Some("claude-3-5-sonnet-latest"), usage(0, M, 0, 0), 15.0),
+            (Some("claude-3-5-haiku-20241022"), usage(0, 0, M, M), 1.0 + 0.08),
+            (Some("claude-haiku-4-5"), usage(2 * M, M, M, 10 * M), 2.0 + 5.0 + 1.25 + 1.0),
+            (Some("gpt-4o"), usage(M, M, M, M), 0.0),
+            (None, usage(M, M, M, M), 0.0),
+        ];
+
+        let table = PricingTable::default();
+        for (model, usage, expected) in cases {
+            let cost = table.cost(*model, usage);
+            assert!((cost - expected).abs() < 1e-9, "{:?}: {} != {}", model, cost, expected);
+        }
+    }
+}
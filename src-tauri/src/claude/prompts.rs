@@ -1,3 +1,7 @@
+use serde_json::json;
+
+use super::types::Tool;
+
 pub const FEATURE_NARRATIVE_SYSTEM: &str = r#"You are analyzing a software feature that was built across multiple commits.
 Given the following data about a feature cluster, generate:
 1. A clear, concise title for this feature (5-10 words)
@@ -5,13 +9,60 @@ Given the following data about a feature cluster, generate:
 3. Key technical decisions made
 4. A list of the most significant function changes and what they do
 
-Respond in JSON format:
-{
-  "title": "...",
-  "narrative": "...",
-  "key_decisions": ["..."],
-  "significant_changes": [{"function": "...", "description": "..."}]
-}"#;
+Record your analysis by calling the record_feature_enrichment tool."#;
+
+/// Narrative-only variant of `FEATURE_NARRATIVE_SYSTEM` whose reply is
+/// streamed as plain text.
+pub const FEATURE_NARRATIVE_STREAM_SYSTEM: &str = r#"You are analyzing a software feature that was built across multiple commits.
+Given the following data about a feature cluster, write a narrative summary (2-3 paragraphs)
+explaining what was built, how it works, and why.
+
+Reply with the narrative only, as plain prose without a title, headings or lists."#;
+
+/// Bump when the narrative prompt or tool schema changes, so cached
+/// enrichments produced by the old prompt are not reused.
+pub const FEATURE_NARRATIVE_PROMPT_VERSION: u32 = 3;
+
+pub const FEATURE_ENRICHMENT_TOOL: &str = "record_feature_enrichment";
+
+/// Tool the model must call to return a feature enrichment.
+pub fn feature_enrichment_tool() -> Tool {
+    Tool {
+        name: FEATURE_ENRICHMENT_TOOL.to_string(),
+        description: "Record the title, narrative, key decisions and significant function \
+                      changes of a feature cluster."
+            .to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Feature title, 5-10 words"
+                },
+                "narrative": {
+                    "type": "string",
+                    "description": "2-3 paragraphs: what was built, how it works, and why"
+                },
+                "key_decisions": {
+                    "type": "array",
+                    "items": { "type": "string" }
+                },
+                "significant_changes": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "function": { "type": "string" },
+                            "description": { "type": "string" }
+                        },
+                        "required": ["function", "description"]
+                    }
+                }
+            },
+            "required": ["title", "narrative", "key_decisions", "significant_changes"]
+        }),
+    }
+}
 
 pub const INTENT_EXTRACTION_SYSTEM: &str = r#"Given the following prompt a developer gave to Claude Code, extract:
 1. The high-level goal (one sentence)
@@ -19,13 +70,45 @@ pub const INTENT_EXTRACTION_SYSTEM: &str = r#"Given the following prompt a devel
 3. Constraints or preferences expressed
 4. The implied context (what problem is being solved)
 
-Respond in JSON format:
-{
-  "goal": "...",
-  "requirements": ["..."],
-  "constraints": ["..."],
-  "context": "..."
-}"#;
+Record your analysis by calling the record_prompt_intent tool."#;
+
+/// Bump when the intent prompt or tool schema changes, so cached intents
+/// produced by the old prompt are not reused.
+pub const INTENT_EXTRACTION_PROMPT_VERSION: u32 = 1;
+
+pub const PROMPT_INTENT_TOOL: &str = "record_prompt_intent";
+
+/// Tool the model must call to return a prompt's intent.
+pub fn prompt_intent_tool() -> Tool {
+    Tool {
+        name: PROMPT_INTENT_TOOL.to_string(),
+        description: "Record the goal, requirements, constraints and context of a \
+                      developer's prompt."
+            .to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "goal": {
+                    "type": "string",
+                    "description": "The high-level goal, in one sentence"
+                },
+                "requirements": {
+                    "type": "array",
+                    "items": { "type": "string" }
+                },
+                "constraints": {
+                    "type": "array",
+                    "items": { "type": "string" }
+                },
+                "context": {
+                    "type": "string",
+                    "description": "The problem being solved"
+                }
+            },
+            "required": ["goal", "requirements", "constraints", "context"]
+        }),
+    }
+}
 
 pub const CROSS_FEATURE_SYSTEM: &str = r#"Given these software features built over time, identify:
 1. Dependencies: Feature X required Feature Y to exist first
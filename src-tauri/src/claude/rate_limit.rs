@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Window over which token usage is counted.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Default budget, matching the lowest API tier's input token limit.
+const DEFAULT_TOKENS_PER_MINUTE: u32 = 40_000;
+
+struct Entry {
+    id: u64,
+    at: Instant,
+    tokens: u32,
+}
+
+struct Window {
+    next_id: u64,
+    entries: VecDeque<Entry>,
+}
+
+impl Window {
+    fn prune(&mut self, now: Instant) {
+        while self
+            .entries
+            .front()
+            .is_some_and(|e| now.duration_since(e.at) >= WINDOW)
+        {
+            self.entries.pop_front();
+        }
+    }
+
+    fn used(&self) -> u32 {
+        self.entries.iter().map(|e| e.tokens).sum()
+    }
+}
+
+/// A slot in the budget held by one in-flight request.
+#[derive(Debug, Clone, Copy)]
+pub struct Reservation(u64);
+
+/// Rolling tokens-per-minute budget shared by concurrent requests.
+///
+/// Requests reserve an estimate before they are sent and settle it with the
+/// usage the API reports, so the window tracks real consumption.
+pub struct TokenBudget {
+    tokens_per_minute: u32,
+    window: Mutex<Window>,
+}
+
+impl TokenBudget {
+    pub fn new(tokens_per_minute: u32) -> Self {
+        Self {
+            tokens_per_minute,
+            window: Mutex::new(Window {
+                next_id: 0,
+                entries: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Budget from `CLAUDE_TOKENS_PER_MINUTE`; 0 disables the limit.
+    pub fn from_env() -> Self {
+        let tokens_per_minute = std::env::var("CLAUDE_TOKENS_PER_MINUTE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TOKENS_PER_MINUTE);
+        Self::new(tokens_per_minute)
+    }
+
+    /// Wait until `estimate` tokens fit in the window, then reserve them. A
+    /// request larger than the whole budget goes through once the window is empty.
+    pub async fn acquire(&self, estimate: u32) -> Reservation {
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut window = self.window.lock().expect("token budget lock poisoned");
+                window.prune(now);
+
+                let fits = self.tokens_per_minute == 0
+                    || window.entries.is_empty()
+                    || window.used().saturating_add(estimate) <= self.tokens_per_minute;
+                if fits {
+                    let id = window.next_id;
+                    window.next_id += 1;
+                    window.entries.push_back(Entry {
+                        id,
+                        at: now,
+                        tokens: estimate,
+                    });
+                    return Reservation(id);
+                }
+
+                // The oldest entry is the next to free up room
+                let oldest = window.entries.front().expect("window is not empty").at;
+                WINDOW.saturating_sub(now.duration_since(oldest))
+            };
+            tokio::time::sleep(wait.max(Duration::from_millis(50))).await;
+        }
+    }
+
+    /// Replace a reservation's estimate with the tokens actually used.
+    pub fn settle(&self, reservation: Reservation, actual: u32) {
+        let mut window = self.window.lock().expect("token budget lock poisoned");
+        if let Some(entry) = window.entries.iter_mut().find(|e| e.id == reservation.0) {
+            entry.tokens = actual;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn settled_usage_frees_room_in_the_window() {
+        let budget = TokenBudget::new(1_000);
+        let first = budget.acquire(800).await;
+
+        // 800 + 300 doesn't fit until the estimate is settled down to 600
+        let blocked = tokio::time::timeout(Duration::from_millis(100), budget.acquire(300)).await;
+        assert!(blocked.is_err());
+        budget.settle(first, 600);
+        let admitted = tokio::time::timeout(Duration::from_millis(100), budget.acquire(300)).await;
+        assert!(admitted.is_ok());
+
+        // An oversized request still goes through on its own
+        let small = TokenBudget::new(100);
+        let alone = tokio::time::timeout(Duration::from_millis(100), small.acquire(5_000)).await;
+        assert!(alone.is_ok());
+    }
+}
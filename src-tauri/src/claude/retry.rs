@@ -0,0 +1,285 @@
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::header::HeaderMap;
+
+use super::error::ClaudeError;
+
+/// Rate-limit buckets reported by the API as `anthropic-ratelimit-<bucket>-{remaining,reset}`.
+const RATE_LIMIT_BUCKETS: &[&str] = &["requests", "tokens", "input-tokens", "output-tokens"];
+/// Upper bound on a server-requested wait, so one response can't stall a batch
+const MAX_SERVER_DELAY: Duration = Duration::from_secs(300);
+
+/// Exponential backoff with full jitter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Default policy, with `CLAUDE_MAX_RETRIES` overriding the retry count.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Some(n) = std::env::var("CLAUDE_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            policy.max_retries = n;
+        }
+        policy
+    }
+
+    /// Random delay in `[0, min(max_delay, base_delay * 2^attempt)]`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let cap = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        cap.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+
+    /// How long to wait before retrying a failed attempt, or `None` when it
+    /// should not be retried.
+    pub fn retry_delay(
+        &self,
+        error: &ClaudeError,
+        hint: &RetryHint,
+        attempt: u32,
+    ) -> Option<Duration> {
+        // The API can veto a retry, e.g. for a rate limit that won't reset soon
+        let retryable = hint.should_retry.unwrap_or_else(|| error.is_retryable());
+        if !retryable || attempt >= self.max_retries {
+            return None;
+        }
+
+        let delay = hint
+            .delay
+            .map(|d| d.min(MAX_SERVER_DELAY))
+            .unwrap_or_else(|| self.backoff(attempt));
+        log::warn!(
+            "API request failed ({}); retry {}/{} in {:.1}s",
+            error,
+            attempt + 1,
+            self.max_retries,
+            delay.as_secs_f64()
+        );
+        Some(delay)
+    }
+
+    /// Run `request` until it succeeds, fails with an error that isn't
+    /// retried, or runs out of retries, sleeping between attempts as
+    /// `retry_delay` says. Each call of `request` is one attempt.
+    pub async fn run<T, F, Fut>(&self, mut request: F) -> Result<T, ClaudeError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, (ClaudeError, RetryHint)>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let (error, hint) = match request().await {
+                Ok(value) => return Ok(value),
+                Err(failure) => failure,
+            };
+            let delay = self.retry_delay(&error, &hint, attempt).ok_or(error)?;
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Retry guidance taken from the headers of a failed response.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RetryHint {
+    /// How long the server asked us to wait
+    pub delay: Option<Duration>,
+    /// Explicit `x-should-retry` verdict
+    pub should_retry: Option<bool>,
+}
+
+impl RetryHint {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            delay: server_retry_delay(headers, Utc::now()),
+            should_retry: should_retry_hint(headers),
+        }
+    }
+}
+
+/// How long the server asked us to wait, if it said so: `retry-after` (seconds
+/// or an HTTP date), or else the latest reset time of an exhausted
+/// `anthropic-ratelimit-*` bucket.
+fn server_retry_delay(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(retry_after) = header("retry-after").map(str::trim) {
+        if let Ok(secs) = retry_after.parse::<f64>() {
+            // Clamp before converting: huge values would overflow a Duration
+            if secs >= 0.0 {
+                if let Ok(delay) =
+                    Duration::try_from_secs_f64(secs.min(MAX_SERVER_DELAY.as_secs_f64()))
+                {
+                    return Some(delay);
+                }
+            }
+        } else if let Ok(date) = DateTime::parse_from_rfc2822(retry_after) {
+            // A date already in the past means "retry now"
+            let wait = date.with_timezone(&Utc) - now;
+            return Some(wait.to_std().unwrap_or(Duration::ZERO));
+        }
+    }
+
+    RATE_LIMIT_BUCKETS
+        .iter()
+        .filter(|bucket| {
+            header(&format!("anthropic-ratelimit-{}-remaining", bucket))
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                == Some(0)
+        })
+        .filter_map(|bucket| header(&format!("anthropic-ratelimit-{}-reset", bucket)))
+        .filter_map(|reset| DateTime::parse_from_rfc3339(reset.trim()).ok())
+        .map(|reset| reset.with_timezone(&Utc) - now)
+        .filter_map(|wait| wait.to_std().ok())
+        .max()
+}
+
+fn should_retry_hint(headers: &HeaderMap) -> Option<bool> {
+    match headers.get("x-should-retry")?.to_str().ok()? {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn retry_after_takes_precedence() {
+        let now = Utc::now();
+        let h = headers(&[
+            ("retry-after", "7"),
+            ("anthropic-ratelimit-tokens-remaining", "0"),
+            ("anthropic-ratelimit-tokens-reset", &(now + chrono::Duration::seconds(30)).to_rfc3339()),
+        ]);
+        assert_eq!(server_retry_delay(&h, now), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn retry_after_accepts_huge_values_and_http_dates() {
+        let now = DateTime::parse_from_rfc3339("2025-10-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let huge = headers(&[("retry-after", "1e30")]);
+        assert_eq!(server_retry_delay(&huge, now), Some(MAX_SERVER_DELAY));
+
+        let date = headers(&[("retry-after", "Wed, 01 Oct 2025 12:00:45 GMT")]);
+        assert_eq!(server_retry_delay(&date, now), Some(Duration::from_secs(45)));
+
+        let past = headers(&[("retry-after", "Wed, 01 Oct 2025 11:59:00 GMT")]);
+        assert_eq!(server_retry_delay(&past, now), Some(Duration::ZERO));
+
+        let garbage = headers(&[("retry-after", "soon")]);
+        assert_eq!(server_retry_delay(&garbage, now), None);
+    }
+
+    #[test]
+    fn waits_for_latest_exhausted_bucket() {
+        let now = DateTime::parse_from_rfc3339("2025-10-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let h = headers(&[
+            ("anthropic-ratelimit-requests-remaining", "0"),
+            ("anthropic-ratelimit-requests-reset", "2025-10-01T12:00:05Z"),
+            ("anthropic-ratelimit-output-tokens-remaining", "0"),
+            ("anthropic-ratelimit-output-tokens-reset", "2025-10-01T12:00:20Z"),
+            // Not exhausted, so its reset is irrelevant
+            ("anthropic-ratelimit-input-tokens-remaining", "1200"),
+            ("anthropic-ratelimit-input-tokens-reset", "2025-10-01T12:01:00Z"),
+        ]);
+        assert_eq!(server_retry_delay(&h, now), Some(Duration::from_secs(20)));
+        assert_eq!(server_retry_delay(&HeaderMap::new(), now), None);
+    }
+
+    #[tokio::test]
+    async fn run_retries_until_success_or_a_final_error() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        let overloaded = || ClaudeError::Overloaded { message: "busy".to_string() };
+
+        let mut calls = 0;
+        let result = policy
+            .run(|| {
+                calls += 1;
+                let outcome = if calls < 3 {
+                    Err((overloaded(), RetryHint::default()))
+                } else {
+                    Ok(calls)
+                };
+                async move { outcome }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let mut calls = 0;
+        let result: Result<(), _> = policy
+            .run(|| {
+                calls += 1;
+                async { Err((overloaded(), RetryHint::default())) }
+            })
+            .await;
+        assert!(matches!(result, Err(ClaudeError::Overloaded { .. })));
+        assert_eq!(calls, 4);
+
+        // The server can veto a retry of an otherwise retryable error
+        let mut calls = 0;
+        let veto = RetryHint { delay: None, should_retry: Some(false) };
+        let result: Result<(), _> = policy
+            .run(|| {
+                calls += 1;
+                async move { Err((overloaded(), veto)) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn backoff_stays_within_the_cap() {
+        let policy = RetryPolicy::default();
+        for attempt in 0..20 {
+            let cap = policy
+                .base_delay
+                .saturating_mul(2u32.saturating_pow(attempt))
+                .min(policy.max_delay);
+            assert!(policy.backoff(attempt) <= cap);
+        }
+    }
+}
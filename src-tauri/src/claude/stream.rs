@@ -0,0 +1,97 @@
+use super::error::ClaudeError;
+use super::types::StreamEvent;
+
+/// Incremental decoder of a `text/event-stream` body into Messages API events.
+///
+/// Network chunks can end anywhere, including inside a UTF-8 sequence, so
+/// bytes are buffered until a blank line completes an event.
+#[derive(Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed a chunk and return the events it completed, in order.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<StreamEvent>, ClaudeError> {
+        self.push_data(chunk)
+            .iter()
+            .map(|data| {
+                serde_json::from_str(data)
+                    .map_err(|e| ClaudeError::Parse(format!("Invalid stream event: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Feed a chunk and return the `data` of each event it completed, for
+    /// streams whose payloads are not Messages API events.
+    pub fn push_data(&mut self, chunk: &[u8]) -> Vec<String> {
+        // Carriage returns only appear in line endings
+        self.buffer.extend(chunk.iter().filter(|&&b| b != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let block = String::from_utf8_lossy(&block[..end]);
+            if let Some(data) = event_data(&block) {
+                events.push(data);
+            }
+        }
+        events
+    }
+}
+
+/// The data of one event block. JSON payloads carry their own `type`, so the
+/// `event:` line is redundant and ignored, as are comments (`: ...`).
+fn event_data(block: &str) -> Option<String> {
+    let data: Vec<&str> = block
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|d| d.strip_prefix(' ').unwrap_or(d))
+        .collect();
+    if data.is_empty() {
+        return None;
+    }
+    Some(data.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::types::ContentDelta;
+
+    #[test]
+    fn decodes_events_split_across_chunks() {
+        let body = "event: message_start\r\n\
+                    data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\r\n\r\n\
+                    event: ping\ndata: {\"type\": \"ping\"}\n\n\
+                    event: content_block_delta\n\
+                    data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Caf\u{e9} \"}}\n\n\
+                    : keep-alive\n\n\
+                    event: message_delta\n\
+                    data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":7}}\n\n\
+                    event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+
+        // One byte at a time splits every event and the two-byte "é"
+        let mut decoder = SseDecoder::default();
+        let mut events = Vec::new();
+        for byte in body.as_bytes() {
+            events.extend(decoder.push(std::slice::from_ref(byte)).unwrap());
+        }
+
+        assert_eq!(events.len(), 5);
+        assert!(
+            matches!(&events[0], StreamEvent::MessageStart { message } if message.usage.input_tokens == 12)
+        );
+        assert!(matches!(events[1], StreamEvent::Other));
+        assert!(matches!(
+            &events[2],
+            StreamEvent::ContentBlockDelta { delta: ContentDelta::TextDelta { text } } if text == "Café "
+        ));
+        assert!(matches!(
+            &events[3],
+            StreamEvent::MessageDelta { delta, usage }
+                if delta.stop_reason.as_deref() == Some("end_turn") && usage.output_tokens == 7
+        ));
+        assert!(matches!(events[4], StreamEvent::MessageStop));
+    }
+}
@@ -1,36 +1,220 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MessageRequest {
     pub model: String,
     pub max_tokens: u32,
-    pub system: String,
+    pub system: Vec<SystemBlock>,
     pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    /// Ask for the reply as server-sent events
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+}
+
+/// A text block of the system prompt. A block with `cache_control` ends a
+/// cached prefix: tools and system blocks up to it are cached for reuse.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemBlock {
+    #[serde(rename = "type")]
+    pub block_type: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+impl SystemBlock {
+    pub fn text(text: &str) -> Self {
+        Self {
+            block_type: "text".to_string(),
+            text: text.to_string(),
+            cache_control: None,
+        }
+    }
+
+    /// A block that ends a cached prefix.
+    pub fn cached(text: &str) -> Self {
+        Self {
+            cache_control: Some(CacheControl::Ephemeral),
+            ..Self::text(text)
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CacheControl {
+    Ephemeral,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
     pub content: String,
 }
 
+/// A client tool the model can call; `input_schema` is a JSON Schema object.
+#[derive(Debug, Clone, Serialize)]
+pub struct Tool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: Value,
+}
+
+/// Only forced calls are used: the reply must be a call to the named tool.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    Tool { name: String },
+}
+
 #[derive(Debug, Deserialize)]
 pub struct MessageResponse {
-    pub id: String,
     pub content: Vec<ContentBlock>,
     pub stop_reason: Option<String>,
     pub usage: Usage,
 }
 
+impl MessageResponse {
+    /// Input of the first call to the named tool.
+    pub fn tool_input(&self, tool_name: &str) -> Option<&Value> {
+        self.content
+            .iter()
+            .find(|block| {
+                block.content_type == "tool_use" && block.name.as_deref() == Some(tool_name)
+            })
+            .and_then(|block| block.input.as_ref())
+    }
+}
+
+/// A content block of a reply. Tool calls (`tool_use`) carry `name` and `input`.
 #[derive(Debug, Deserialize)]
 pub struct ContentBlock {
     #[serde(rename = "type")]
     pub content_type: String,
-    pub text: Option<String>,
+    pub name: Option<String>,
+    pub input: Option<Value>,
 }
 
+/// Token usage of a response. `input_tokens` excludes the prompt tokens
+/// written to or read from the cache.
 #[derive(Debug, Deserialize)]
 pub struct Usage {
     pub input_tokens: u32,
     pub output_tokens: u32,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u32,
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
+}
+
+/// One server-sent event of a streamed message. Events the client doesn't
+/// use (`content_block_start`, `content_block_stop`, `ping`, and any added
+/// later) are `Other`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    MessageStart {
+        message: StreamMessage,
+    },
+    ContentBlockDelta {
+        delta: ContentDelta,
+    },
+    MessageDelta {
+        delta: MessageDeltaBody,
+        usage: DeltaUsage,
+    },
+    MessageStop,
+    Error {
+        error: StreamError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamMessage {
+    pub usage: Usage,
+}
+
+/// Increment of a content block. Only text is streamed; tool input deltas
+/// (`input_json_delta`) are `Other`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentDelta {
+    TextDelta {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MessageDeltaBody {
+    pub stop_reason: Option<String>,
+}
+
+/// Cumulative output tokens, reported in `message_delta`.
+#[derive(Debug, Deserialize)]
+pub struct DeltaUsage {
+    pub output_tokens: u32,
+}
+
+/// An error reported mid-stream, after the response status was already 200.
+#[derive(Debug, Deserialize)]
+pub struct StreamError {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub message: String,
+}
+
+/// One request of a message batch. `custom_id` matches it to its result.
+#[derive(Debug, Serialize)]
+pub struct BatchRequest {
+    pub custom_id: String,
+    pub params: MessageRequest,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateBatchRequest {
+    pub requests: Vec<BatchRequest>,
+}
+
+/// A Message Batches API batch. `processing_status` is `in_progress`,
+/// `canceling` or `ended`; results can be fetched once it has ended.
+#[derive(Debug, Deserialize)]
+pub struct MessageBatch {
+    pub id: String,
+    pub processing_status: String,
+    pub request_counts: BatchRequestCounts,
+    pub results_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BatchRequestCounts {
+    pub processing: u32,
+    pub succeeded: u32,
+    pub errored: u32,
+    pub canceled: u32,
+    pub expired: u32,
+}
+
+/// One line of a batch's JSONL results.
+#[derive(Debug, Deserialize)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub result: BatchOutcome,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOutcome {
+    Succeeded { message: MessageResponse },
+    Errored { error: Value },
+    Canceled,
+    Expired,
 }
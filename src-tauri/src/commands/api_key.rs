@@ -0,0 +1,77 @@
+use crate::claude::client::ClaudeClient;
+use crate::storage::secrets::{secret_store, API_KEY_SECRET};
+use crate::types::ApiKeyStatus;
+
+use super::enrich::api_key;
+use super::settings::resolved_settings;
+
+/// Tauri command: where the API key comes from and its last characters.
+#[tauri::command]
+pub async fn get_api_key_status() -> Result<ApiKeyStatus, String> {
+    api_key_status()
+}
+
+/// Tauri command: save the API key in the secret store. `ANTHROPIC_API_KEY`
+/// still takes precedence when set.
+#[tauri::command]
+pub async fn set_api_key(key: String) -> Result<ApiKeyStatus, String> {
+    let key = key.trim();
+    if key.is_empty() || key.chars().any(char::is_whitespace) {
+        return Err("The API key must be a single word".to_string());
+    }
+    let store = secret_store()?;
+    store.set(API_KEY_SECRET, key)?;
+    log::info!("Saved the API key in the {}", store.kind());
+    api_key_status()
+}
+
+/// Tauri command: remove the API key from the secret store.
+#[tauri::command]
+pub async fn clear_api_key() -> Result<ApiKeyStatus, String> {
+    secret_store()?.delete(API_KEY_SECRET)?;
+    log::info!("Removed the API key");
+    api_key_status()
+}
+
+/// Tauri command: check `key`, or the configured key, against the API with
+/// the current connection settings. Uses no tokens.
+#[tauri::command]
+pub async fn test_api_key(key: Option<String>) -> Result<(), String> {
+    let settings = resolved_settings()?;
+    let key = match key.map(|k| k.trim().to_string()).filter(|k| !k.is_empty()) {
+        Some(key) => key,
+        None => api_key(&settings)?,
+    };
+    ClaudeClient::new(key, settings.claude_model, &settings.api)?
+        .check_key()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn api_key_status() -> Result<ApiKeyStatus, String> {
+    if let Some(key) = std::env::var("ANTHROPIC_API_KEY").ok().filter(|k| !k.is_empty()) {
+        return Ok(ApiKeyStatus {
+            source: Some("environment".to_string()),
+            hint: key_hint(&key),
+        });
+    }
+    let store = secret_store()?;
+    Ok(match store.get(API_KEY_SECRET)? {
+        Some(key) => ApiKeyStatus {
+            source: Some(store.kind().to_string()),
+            hint: key_hint(&key),
+        },
+        None => ApiKeyStatus::default(),
+    })
+}
+
+/// The last four characters of a key, if it is long enough to keep the rest
+/// secret.
+fn key_hint(key: &str) -> Option<String> {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() < 16 {
+        return None;
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    Some(format!("…{}", tail))
+}
@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::claude::client::ClaudeClient;
+use crate::claude::types::{BatchOutcome, BatchRequest};
+use crate::storage::db::Database;
+use crate::types::{EnrichmentBatch, EnrichmentBatchItem, ProjectData};
+
+use super::claude_api::{
+    apply_cached_enrichments, cache_enrichment, enrichment_cache_keys, enrichment_from_response,
+    enrichment_request, repo_preamble, FeatureEnrichment,
+};
+use super::enrich::{claude_client, commit_subjects, enrichment_targets};
+use super::settings::resolved_settings;
+
+/// Event carrying an `EnrichmentBatch` whenever a batch's status changes.
+pub const ENRICHMENT_BATCH_EVENT: &str = "enrichment-batch";
+
+/// Requests per submitted batch; the API accepts up to 100,000 (and 256 MB).
+const MAX_BATCH_REQUESTS: usize = 10_000;
+/// Batches usually end within an hour, so there is no point polling faster.
+const BATCH_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Set while a `resume_enrichment_batches` loop is running.
+static POLLING: AtomicBool = AtomicBool::new(false);
+
+/// Tauri command: submit a stored project's features to the Message Batches
+/// API for enrichment at batch pricing, and start following the batches.
+///
+/// Targets are chosen like `enrich_features`. Cached enrichments are applied
+/// right away unless `force` is set; only the rest are submitted. Results are
+/// applied to the stored project when the batch ends, even after a restart.
+/// Only available with the Anthropic provider.
+#[tauri::command]
+pub async fn submit_enrichment_batch(
+    app: AppHandle,
+    project_id: String,
+    feature_ids: Option<Vec<i32>>,
+    force: Option<bool>,
+) -> Result<Vec<EnrichmentBatch>, String> {
+    let settings = resolved_settings()?;
+    if settings.provider != "anthropic" {
+        return Err("Batch enrichment needs the anthropic provider".into());
+    }
+    let client = claude_client(&settings)?;
+    let mut project = Database::open_default()?.load_project(&project_id)?;
+    let targets = enrichment_targets(&project, feature_ids)?;
+    let commit_subjects = commit_subjects(&project);
+
+    let mut keys =
+        enrichment_cache_keys(client.model(), &project.features, &commit_subjects, &targets);
+    if !force.unwrap_or(false) {
+        let db = Database::open_default()?;
+        let restored = apply_cached_enrichments(&db, &mut project.features, &keys);
+        if !restored.is_empty() {
+            db.update_project(&project_id, &project)?;
+        }
+        keys.retain(|id, _| !restored.contains(id));
+    }
+    submit(&app, &client, &project, keys, &commit_subjects).await
+}
+
+/// Submit the keyed features in batches of up to `MAX_BATCH_REQUESTS`.
+async fn submit(
+    app: &AppHandle,
+    client: &ClaudeClient,
+    project: &ProjectData,
+    keys: HashMap<i32, String>,
+    commit_subjects: &HashMap<String, String>,
+) -> Result<Vec<EnrichmentBatch>, String> {
+    let project_id = project.id.clone().unwrap_or_default();
+    let preamble = repo_preamble(project);
+    let features: Vec<_> = project
+        .features
+        .iter()
+        .filter(|f| keys.contains_key(&f.cluster_id))
+        .collect();
+
+    let mut batches = Vec::new();
+    for chunk in features.chunks(MAX_BATCH_REQUESTS) {
+        let items: Vec<EnrichmentBatchItem> = chunk
+            .iter()
+            .map(|f| EnrichmentBatchItem {
+                custom_id: format!("feature-{}", f.cluster_id),
+                cluster_id: f.cluster_id,
+                cache_key: keys[&f.cluster_id].clone(),
+            })
+            .collect();
+        let requests = chunk
+            .iter()
+            .zip(&items)
+            .map(|(feature, item)| BatchRequest {
+                custom_id: item.custom_id.clone(),
+                params: enrichment_request(client, &preamble, feature, commit_subjects),
+            })
+            .collect();
+
+        let remote = client.create_batch(requests).await?;
+        let batch = EnrichmentBatch {
+            batch_id: remote.id,
+            project_id: project_id.clone(),
+            model: client.model().to_string(),
+            status: remote.processing_status,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            processing: items.len() as u32,
+            items,
+            succeeded: 0,
+            errored: 0,
+            expired: 0,
+            applied: 0,
+            error: None,
+        };
+        Database::open_default()?.save_batch(&batch)?;
+        log::info!(
+            "Submitted enrichment batch {} with {} features",
+            batch.batch_id,
+            batch.items.len()
+        );
+        emit_batch(app, &batch);
+        batches.push(batch);
+    }
+
+    if !batches.is_empty() {
+        tauri::async_runtime::spawn(resume_enrichment_batches(app.clone()));
+    }
+    Ok(batches)
+}
+
+/// Tauri command: enrichment batches submitted for a project, newest first.
+#[tauri::command]
+pub async fn list_enrichment_batches(project_id: String) -> Result<Vec<EnrichmentBatch>, String> {
+    Database::open_default()?.list_batches(&project_id)
+}
+
+/// Tauri command: check every unapplied batch now, applying the results of
+/// those that ended. Returns the checked batches.
+#[tauri::command]
+pub async fn poll_enrichment_batches(app: AppHandle) -> Result<Vec<EnrichmentBatch>, String> {
+    poll_pending_batches(&app).await
+}
+
+/// Poll unapplied batches until all are applied. Started at launch to pick up
+/// batches submitted before a restart, and after each submission; only one
+/// loop runs at a time.
+pub async fn resume_enrichment_batches(app: AppHandle) {
+    if POLLING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    loop {
+        match poll_pending_batches(&app).await {
+            Ok(batches) if batches.iter().all(|b| b.status == "applied") => break,
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("Stopped following enrichment batches: {}", e);
+                break;
+            }
+        }
+        tokio::time::sleep(BATCH_POLL_INTERVAL).await;
+    }
+    POLLING.store(false, Ordering::SeqCst);
+}
+
+/// Check each unapplied batch once. A batch that fails to update keeps its
+/// status and records the error, to be retried on the next poll.
+async fn poll_pending_batches(app: &AppHandle) -> Result<Vec<EnrichmentBatch>, String> {
+    let mut batches = Database::open_default()?.pending_batches()?;
+    if batches.is_empty() {
+        return Ok(batches);
+    }
+    let client = claude_client(&resolved_settings()?)?;
+
+    for batch in batches.iter_mut() {
+        let previous = (batch.status.clone(), batch.processing);
+        batch.error = match poll_batch(&client, batch).await {
+            Ok(()) => None,
+            Err(e) => {
+                log::warn!("Failed to update enrichment batch {}: {}", batch.batch_id, e);
+                Some(e)
+            }
+        };
+        Database::open_default()?.save_batch(batch)?;
+        if (batch.status.clone(), batch.processing) != previous || batch.error.is_some() {
+            emit_batch(app, batch);
+        }
+    }
+    Ok(batches)
+}
+
+/// Refresh a batch's status and, once it has ended, apply its results.
+async fn poll_batch(client: &ClaudeClient, batch: &mut EnrichmentBatch) -> Result<(), String> {
+    let remote = client.get_batch(&batch.batch_id).await?;
+    let counts = &remote.request_counts;
+    batch.status = remote.processing_status.clone();
+    batch.processing = counts.processing;
+    batch.succeeded = counts.succeeded;
+    batch.errored = counts.errored;
+    batch.expired = counts.canceled + counts.expired;
+    if remote.processing_status != "ended" {
+        return Ok(());
+    }
+
+    let mut enrichments = Vec::new();
+    for result in client.batch_results(&remote).await? {
+        match result.result {
+            BatchOutcome::Succeeded { message } => match enrichment_from_response(&message) {
+                Ok(enrichment) => enrichments.push((result.custom_id, enrichment)),
+                Err(e) => {
+                    log::warn!("Invalid enrichment for {}: {}", result.custom_id, e);
+                    batch.succeeded = batch.succeeded.saturating_sub(1);
+                    batch.errored += 1;
+                }
+            },
+            BatchOutcome::Errored { error } => {
+                log::warn!("Batch request {} failed: {}", result.custom_id, error);
+            }
+            BatchOutcome::Canceled | BatchOutcome::Expired => {}
+        }
+    }
+    apply_batch_results(&Database::open_default()?, batch, &enrichments)
+}
+
+/// Cache each enrichment under the key its feature had at submission, then
+/// restore the stored project's features from those keys. Features that
+/// changed since the submission no longer match and are left alone.
+/// The batch is only marked applied once that succeeds, so a failed write is
+/// retried on the next poll.
+fn apply_batch_results(
+    db: &Database,
+    batch: &mut EnrichmentBatch,
+    enrichments: &[(String, FeatureEnrichment)],
+) -> Result<(), String> {
+    for (custom_id, enrichment) in enrichments {
+        if let Some(item) = batch.items.iter().find(|i| &i.custom_id == custom_id) {
+            cache_enrichment(db, &item.cache_key, enrichment)?;
+        }
+    }
+
+    let mut project = match db.load_project(&batch.project_id) {
+        Ok(project) => project,
+        // A deleted project is not retried; the results stay cached for its next scan
+        Err(e) if e.starts_with("Project not found") => {
+            log::info!("Project {} of batch {} is gone", batch.project_id, batch.batch_id);
+            batch.status = "applied".to_string();
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    // A rescan can renumber features, so match on keys rather than ids
+    let batch_keys: HashSet<&str> = batch.items.iter().map(|i| i.cache_key.as_str()).collect();
+    let all: HashSet<i32> = project.features.iter().map(|f| f.cluster_id).collect();
+    let keys: HashMap<i32, String> =
+        enrichment_cache_keys(&batch.model, &project.features, &commit_subjects(&project), &all)
+            .into_iter()
+            .filter(|(_, key)| batch_keys.contains(key.as_str()))
+            .collect();
+    let applied = apply_cached_enrichments(db, &mut project.features, &keys);
+    db.update_project(&batch.project_id, &project)?;
+
+    batch.status = "applied".to_string();
+    batch.applied = applied.len() as u32;
+    log::info!(
+        "Applied {} enrichments from batch {} to project {}",
+        batch.applied,
+        batch.batch_id,
+        batch.project_id
+    );
+    Ok(())
+}
+
+fn emit_batch(app: &AppHandle, batch: &EnrichmentBatch) {
+    if let Err(e) = app.emit(ENRICHMENT_BATCH_EVENT, batch.clone()) {
+        log::warn!("Failed to emit enrichment batch status: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FeatureCluster;
+
+    fn temp_db() -> (std::path::PathBuf, Database) {
+        let dir = std::env::temp_dir().join(format!("codelens-batch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::open(&dir.join("test.db")).unwrap();
+        (dir, db)
+    }
+
+    fn feature(cluster_id: i32, commit: &str) -> FeatureCluster {
+        FeatureCluster {
+            cluster_id,
+            auto_label: commit.to_string(),
+            commit_hashes: vec![commit.to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn project(features: Vec<FeatureCluster>) -> ProjectData {
+        let mut project = ProjectData { features, ..Default::default() };
+        project.repository.path = "/repo".to_string();
+        project
+    }
+
+    fn enrichment(title: &str) -> FeatureEnrichment {
+        FeatureEnrichment {
+            title: title.to_string(),
+            narrative: format!("{} narrative", title),
+            key_decisions: Vec::new(),
+            significant_changes: Vec::new(),
+        }
+    }
+
+    /// A batch submitted for `project`, with one item per feature.
+    fn batch(project_id: &str, project: &ProjectData) -> EnrichmentBatch {
+        let all = project.features.iter().map(|f| f.cluster_id).collect();
+        let keys = enrichment_cache_keys("claude-test", &project.features, &HashMap::new(), &all);
+        let mut items: Vec<EnrichmentBatchItem> = keys
+            .into_iter()
+            .map(|(cluster_id, cache_key)| EnrichmentBatchItem {
+                custom_id: format!("feature-{}", cluster_id),
+                cluster_id,
+                cache_key,
+            })
+            .collect();
+        items.sort_by_key(|i| i.cluster_id);
+        EnrichmentBatch {
+            batch_id: "b1".to_string(),
+            project_id: project_id.to_string(),
+            model: "claude-test".to_string(),
+            status: "ended".to_string(),
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            items,
+            processing: 0,
+            succeeded: 2,
+            errored: 0,
+            expired: 0,
+            applied: 0,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn results_follow_features_renumbered_by_a_rescan() {
+        let (dir, db) = temp_db();
+        let submitted = project(vec![feature(0, "aaa"), feature(1, "bbb"), feature(2, "ccc")]);
+        let mut batch = batch("p1", &submitted);
+
+        // The rescan swapped the first two ids and changed the third feature
+        let mut rescanned = project(vec![feature(0, "bbb"), feature(1, "aaa"), feature(2, "ddd")]);
+        let id = db.save_project(&mut rescanned).unwrap();
+        batch.project_id = id.clone();
+
+        let results = [
+            ("feature-0".to_string(), enrichment("Auth")),
+            ("feature-1".to_string(), enrichment("Search")),
+            ("feature-2".to_string(), enrichment("Stale")),
+        ];
+        apply_batch_results(&db, &mut batch, &results).unwrap();
+
+        let stored = db.load_project(&id).unwrap();
+        let titles: Vec<Option<&str>> =
+            stored.features.iter().map(|f| f.title.as_deref()).collect();
+        assert_eq!(titles, [Some("Search"), Some("Auth"), None]);
+        assert_eq!(batch.status, "applied");
+        assert_eq!(batch.applied, 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn results_for_a_deleted_project_are_cached_and_not_retried() {
+        let (dir, db) = temp_db();
+        let submitted = project(vec![feature(0, "aaa")]);
+        let mut batch = batch("gone", &submitted);
+
+        let results = [("feature-0".to_string(), enrichment("Auth"))];
+        apply_batch_results(&db, &mut batch, &results).unwrap();
+        assert_eq!(batch.status, "applied");
+        assert_eq!(batch.applied, 0);
+
+        // A later scan of the project still picks the result up from the cache
+        let mut features = submitted.features.clone();
+        let keys: HashMap<i32, String> =
+            batch.items.iter().map(|i| (i.cluster_id, i.cache_key.clone())).collect();
+        assert_eq!(apply_cached_enrichments(&db, &mut features, &keys).len(), 1);
+        assert_eq!(features[0].title.as_deref(), Some("Auth"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unreadable_projects_leave_the_batch_pending() {
+        let (dir, db) = temp_db();
+        let mut stored = project(vec![feature(0, "aaa")]);
+        let id = db.save_project(&mut stored).unwrap();
+        db.conn()
+            .execute("UPDATE projects SET data_json = '{' WHERE id = ?1", [&id])
+            .unwrap();
+        let mut batch = batch(&id, &stored);
+
+        let results = [("feature-0".to_string(), enrichment("Auth"))];
+        assert!(apply_batch_results(&db, &mut batch, &results).is_err());
+        assert_eq!(batch.status, "ended");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
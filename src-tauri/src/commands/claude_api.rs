@@ -1,24 +1,49 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::claude::client::ClaudeClient;
-use crate::claude::prompts::FEATURE_NARRATIVE_SYSTEM;
-use crate::types::FeatureCluster;
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
-/// Response from Claude API for feature enrichment.
-#[derive(Debug, serde::Deserialize)]
+use crate::claude::client::{forced_tool_input, ClaudeClient};
+use crate::claude::error::ClaudeError;
+use crate::claude::pricing::PricingTable;
+use crate::claude::prompts::{
+    feature_enrichment_tool, prompt_intent_tool, FEATURE_ENRICHMENT_TOOL,
+    FEATURE_NARRATIVE_PROMPT_VERSION,
+    FEATURE_NARRATIVE_STREAM_SYSTEM, FEATURE_NARRATIVE_SYSTEM, INTENT_EXTRACTION_PROMPT_VERSION,
+    INTENT_EXTRACTION_SYSTEM,
+};
+use crate::claude::types::{MessageRequest, MessageResponse, SystemBlock};
+use crate::llm::LlmProvider;
+use crate::storage::db::Database;
+use crate::types::{
+    EnrichmentProgress, FeatureCluster, IntentProgress, ProjectData, PromptIntent, PromptSession,
+    SignificantChange,
+};
+
+/// How long a cached enrichment or intent is reused before it is requested again.
+const ENRICHMENT_CACHE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Cache entries kept after eviction, across all cached values.
+const MAX_CACHE_ENTRIES: usize = 5000;
+const ENRICHMENT_MAX_TOKENS: u32 = 2048;
+/// Features listed in the repo preamble; longer histories are truncated.
+const MAX_PREAMBLE_FEATURES: usize = 200;
+
+/// Input of the `record_feature_enrichment` tool call.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct FeatureEnrichment {
     pub title: String,
     pub narrative: String,
     pub key_decisions: Vec<String>,
+    pub significant_changes: Vec<SignificantChange>,
 }
 
-/// Enrich a single feature cluster using Claude API.
-pub async fn enrich_feature(
-    client: &ClaudeClient,
-    feature: &FeatureCluster,
-    commit_details: &str,
-) -> Result<FeatureEnrichment, String> {
-    let user_message = format!(
+/// Feature data sent to Claude as the user message.
+fn feature_message(feature: &FeatureCluster, commit_details: &str) -> String {
+    format!(
         "Feature #{} — {} commits, {} lines added, {} lines removed\n\
          Time range: {} to {}\n\
          Primary files: {}\n\
@@ -33,77 +58,792 @@ pub async fn enrich_feature(
         feature.primary_files.join(", "),
         feature.auto_label,
         commit_details,
+    )
+}
+
+/// Repository-level context shared by every feature of a project: repo
+/// info, languages, conventions and the list of features. It is identical for
+/// all requests of a run, so it is sent as a cached system prefix.
+pub fn repo_preamble(project: &ProjectData) -> String {
+    let repo = &project.repository;
+    let mut preamble = format!(
+        "Repository: {}\n\
+         History: {} commits from {} to {}\n\
+         Languages: {}\n",
+        repo.name,
+        repo.total_commits,
+        repo.date_range.start,
+        repo.date_range.end,
+        repo.languages_detected.join(", "),
     );
 
-    let response = client
-        .send_message(FEATURE_NARRATIVE_SYSTEM, &user_message, 1024)
+    let mut dirs: HashMap<&str, u32> = HashMap::new();
+    for file in project.commits.iter().flat_map(|c| &c.files_changed) {
+        if let Some((dir, _)) = file.path.split_once('/') {
+            *dirs.entry(dir).or_insert(0) += 1;
+        }
+    }
+    let mut dirs: Vec<(&str, u32)> = dirs.into_iter().collect();
+    dirs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    if !dirs.is_empty() {
+        let names: Vec<&str> = dirs.iter().take(10).map(|(d, _)| *d).collect();
+        preamble.push_str(&format!("Top-level directories: {}\n", names.join(", ")));
+    }
+    if !project.analytics.most_modified_files.is_empty() {
+        preamble.push_str(&format!(
+            "Most modified files: {}\n",
+            project.analytics.most_modified_files.join(", ")
+        ));
+    }
+
+    let mut change_types: Vec<(&String, &u32)> =
+        project.analytics.change_type_totals.iter().collect();
+    change_types.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    if !change_types.is_empty() {
+        let mix: Vec<String> = change_types
+            .iter()
+            .map(|(t, n)| format!("{} {}", t, n))
+            .collect();
+        preamble.push_str(&format!("Commits by change type: {}\n", mix.join(", ")));
+    }
+
+    let conventional = project
+        .commits
+        .iter()
+        .filter(|c| is_conventional_subject(&c.subject))
+        .count();
+    if !project.commits.is_empty() && conventional * 2 > project.commits.len() {
+        preamble.push_str("Commit messages follow Conventional Commits (type(scope): subject)\n");
+    }
+
+    preamble.push_str("\nFeatures in this history:\n");
+    for feature in project.features.iter().take(MAX_PREAMBLE_FEATURES) {
+        preamble.push_str(&format!(
+            "- #{} {} ({} to {})\n",
+            feature.cluster_id, feature.auto_label, feature.time_start, feature.time_end
+        ));
+    }
+    if project.features.len() > MAX_PREAMBLE_FEATURES {
+        preamble.push_str(&format!(
+            "- ... and {} more\n",
+            project.features.len() - MAX_PREAMBLE_FEATURES
+        ));
+    }
+    preamble
+}
+
+/// A subject like `feat: ...`, `fix(parser): ...` or `refactor!: ...`.
+fn is_conventional_subject(subject: &str) -> bool {
+    let Some((prefix, _)) = subject.split_once(": ") else {
+        return false;
+    };
+    let prefix = prefix.trim_end_matches('!');
+    let kind = match prefix.split_once('(') {
+        Some((kind, scope)) if scope.ends_with(')') => kind,
+        Some(_) => return false,
+        None => prefix,
+    };
+    !kind.is_empty() && kind.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// System prompt of a feature request: the instructions, then the repo
+/// preamble, which ends the cached prefix.
+fn feature_system(instructions: &str, preamble: &str) -> Vec<SystemBlock> {
+    vec![SystemBlock::text(instructions), SystemBlock::cached(preamble)]
+}
+
+/// Enrich a single feature cluster with the provider's model.
+pub async fn enrich_feature(
+    client: &dyn LlmProvider,
+    preamble: &str,
+    feature: &FeatureCluster,
+    commit_details: &str,
+) -> Result<FeatureEnrichment, ClaudeError> {
+    let input = client
+        .call_tool(
+            &feature_system(FEATURE_NARRATIVE_SYSTEM, preamble),
+            &feature_message(feature, commit_details),
+            ENRICHMENT_MAX_TOKENS,
+            feature_enrichment_tool(),
+        )
         .await?;
 
-    // Parse JSON response from Claude
-    serde_json::from_str::<FeatureEnrichment>(&response).map_err(|e| {
-        // If JSON parsing fails, try to extract from markdown code block
-        if let Some(json_start) = response.find('{') {
-            if let Some(json_end) = response.rfind('}') {
-                let json_str = &response[json_start..=json_end];
-                return serde_json::from_str::<FeatureEnrichment>(json_str)
-                    .map_err(|e2| format!("Failed to parse enrichment response: {}", e2))
-                    .err()
-                    .unwrap_or_else(|| format!("Parse error: {}", e));
-            }
+    parse_enrichment(input)
+}
+
+/// The request `enrich_feature` sends, for submission in a message batch.
+pub fn enrichment_request(
+    client: &ClaudeClient,
+    preamble: &str,
+    feature: &FeatureCluster,
+    commit_subjects: &HashMap<String, String>,
+) -> MessageRequest {
+    let details = commit_details(feature, commit_subjects);
+    client.tool_request(
+        &feature_system(FEATURE_NARRATIVE_SYSTEM, preamble),
+        &feature_message(feature, &details),
+        ENRICHMENT_MAX_TOKENS,
+        feature_enrichment_tool(),
+    )
+}
+
+/// The enrichment in a reply to an `enrichment_request`.
+pub fn enrichment_from_response(
+    response: &MessageResponse,
+) -> Result<FeatureEnrichment, ClaudeError> {
+    forced_tool_input(response, FEATURE_ENRICHMENT_TOOL).and_then(parse_enrichment)
+}
+
+/// Write a feature's narrative, passing the text to `on_text` as it streams in.
+pub async fn stream_narrative(
+    client: &dyn LlmProvider,
+    preamble: &str,
+    feature: &FeatureCluster,
+    commit_subjects: &HashMap<String, String>,
+    mut on_text: impl FnMut(&str) + Send,
+) -> Result<String, ClaudeError> {
+    let details = commit_details(feature, commit_subjects);
+    client
+        .stream_message(
+            &feature_system(FEATURE_NARRATIVE_STREAM_SYSTEM, preamble),
+            &feature_message(feature, &details),
+            1024,
+            &mut on_text,
+        )
+        .await
+        .map(|text| text.trim().to_string())
+}
+
+/// Check a tool input against the enrichment schema.
+fn parse_enrichment(input: serde_json::Value) -> Result<FeatureEnrichment, ClaudeError> {
+    serde_json::from_value(input)
+        .map_err(|e| ClaudeError::Parse(format!("Invalid enrichment tool input: {}", e)))
+}
+
+/// Cache key of a feature's enrichment: a hash of everything that shapes the
+/// prompt's substance, so a rescan of unchanged commits hits the cache.
+fn enrichment_cache_key(model: &str, feature: &FeatureCluster, details: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0]);
+    hasher.update(FEATURE_NARRATIVE_PROMPT_VERSION.to_le_bytes());
+    for hash in &feature.commit_hashes {
+        hasher.update(hash.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(details.as_bytes());
+    format!("enrichment:{}", hex::encode(hasher.finalize()))
+}
+
+/// Cache keys of the `targets` features, by cluster id.
+pub fn enrichment_cache_keys(
+    model: &str,
+    features: &[FeatureCluster],
+    commit_subjects: &HashMap<String, String>,
+    targets: &HashSet<i32>,
+) -> HashMap<i32, String> {
+    features
+        .iter()
+        .filter(|f| targets.contains(&f.cluster_id))
+        .map(|f| {
+            let details = commit_details(f, commit_subjects);
+            (f.cluster_id, enrichment_cache_key(model, f, &details))
+        })
+        .collect()
+}
+
+fn apply_enrichment(feature: &mut FeatureCluster, enrichment: FeatureEnrichment) {
+    feature.title = Some(enrichment.title);
+    feature.narrative = Some(enrichment.narrative);
+    feature.key_decisions = enrichment.key_decisions;
+    feature.significant_changes = enrichment.significant_changes;
+}
+
+/// Fill features from cached enrichments. Returns the cluster ids restored.
+pub fn apply_cached_enrichments(
+    db: &Database,
+    features: &mut [FeatureCluster],
+    keys: &HashMap<i32, String>,
+) -> HashSet<i32> {
+    let mut restored = HashSet::new();
+    for feature in features.iter_mut() {
+        let Some(key) = keys.get(&feature.cluster_id) else {
+            continue;
+        };
+        let Some(enrichment) = db
+            .cache_get(key)
+            .and_then(|value| serde_json::from_str::<FeatureEnrichment>(&value).ok())
+        else {
+            continue;
+        };
+        apply_enrichment(feature, enrichment);
+        restored.insert(feature.cluster_id);
+    }
+    restored
+}
+
+/// Cache the enrichments of keyed features that now have one, skipping those
+/// that came from the cache, then evict stale entries.
+pub fn store_enrichments(
+    db: &Database,
+    features: &[FeatureCluster],
+    keys: &HashMap<i32, String>,
+    skip: &HashSet<i32>,
+) -> Result<(), String> {
+    for feature in features.iter().filter(|f| !skip.contains(&f.cluster_id)) {
+        let (Some(key), Some(title), Some(narrative)) =
+            (keys.get(&feature.cluster_id), &feature.title, &feature.narrative)
+        else {
+            continue;
+        };
+        let enrichment = FeatureEnrichment {
+            title: title.clone(),
+            narrative: narrative.clone(),
+            key_decisions: feature.key_decisions.clone(),
+            significant_changes: feature.significant_changes.clone(),
+        };
+        cache_enrichment(db, key, &enrichment)?;
+    }
+    db.cache_evict(MAX_CACHE_ENTRIES)?;
+    Ok(())
+}
+
+/// Cache one enrichment under its key.
+pub fn cache_enrichment(
+    db: &Database,
+    key: &str,
+    enrichment: &FeatureEnrichment,
+) -> Result<(), String> {
+    let value = serde_json::to_string(enrichment)
+        .map_err(|e| format!("Failed to serialize enrichment: {}", e))?;
+    db.cache_set(key, &value, Some(ENRICHMENT_CACHE_TTL))
+}
+
+/// Commit lines sent to Claude as context for one feature.
+fn commit_details(feature: &FeatureCluster, commit_subjects: &HashMap<String, String>) -> String {
+    let mut details = String::new();
+    for hash in &feature.commit_hashes {
+        if let Some(subject) = commit_subjects.get(hash) {
+            details.push_str(&format!("- {} {}\n", &hash[..7.min(hash.len())], subject));
+        }
+    }
+    details
+}
+
+/// Run `call` on every item with at most `max_concurrent` calls in flight.
+/// `on_result` gets each item's index and result in completion order. An auth
+/// error cancels the remaining calls and is returned, since it would fail them
+/// all the same way.
+async fn run_concurrently<T, R, F, Fut>(
+    items: Vec<T>,
+    max_concurrent: usize,
+    call: F,
+    mut on_result: impl FnMut(usize, Result<R, ClaudeError>),
+) -> Result<(), String>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = Result<R, ClaudeError>> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let mut tasks = JoinSet::new();
+
+    for (index, item) in items.into_iter().enumerate() {
+        let semaphore = Arc::clone(&semaphore);
+        let pending = call(item);
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("batch semaphore is never closed");
+            (index, pending.await)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) = joined.map_err(|e| format!("LLM request task failed: {}", e))?;
+        if let Err(e @ ClaudeError::Auth { .. }) = result {
+            tasks.abort_all();
+            return Err(e.into());
         }
-        format!("Failed to parse enrichment response: {}", e)
-    })
+        on_result(index, result);
+    }
+    Ok(())
 }
 
-/// Enrich multiple features with rate limiting (max concurrent calls).
+/// Enrich the `targets` features, running up to `max_concurrent` requests at
+/// once. `on_progress` is called as each feature finishes, in completion order.
 pub async fn enrich_features_batch(
-    client: &ClaudeClient,
+    client: Arc<dyn LlmProvider>,
+    preamble: &str,
     features: &mut [FeatureCluster],
+    targets: &HashSet<i32>,
     commit_subjects: &HashMap<String, String>,
     max_concurrent: usize,
+    on_progress: impl Fn(EnrichmentProgress),
 ) -> Result<u32, String> {
-    use tokio::sync::Semaphore;
-    use std::sync::Arc;
+    let indices: Vec<usize> = (0..features.len())
+        .filter(|&i| targets.contains(&features[i].cluster_id))
+        .collect();
+    let requests: Vec<(FeatureCluster, String)> = indices
+        .iter()
+        .map(|&i| {
+            let feature = features[i].clone();
+            let details = commit_details(&feature, commit_subjects);
+            (feature, details)
+        })
+        .collect();
 
-    let semaphore = Arc::new(Semaphore::new(max_concurrent));
+    let total = requests.len() as u32;
+    let mut completed: u32 = 0;
     let mut enriched_count: u32 = 0;
+    let preamble: Arc<str> = Arc::from(preamble);
+    let pricing = PricingTable::load();
 
-    // Process features sequentially with semaphore for rate limiting
-    for feature in features.iter_mut() {
-        // Skip already enriched features
-        if feature.title.is_some() {
+    run_concurrently(
+        requests,
+        max_concurrent,
+        |(feature, details)| {
+            let client = Arc::clone(&client);
+            let preamble = Arc::clone(&preamble);
+            async move { enrich_feature(&*client, &preamble, &feature, &details).await }
+        },
+        |n, result| {
+            let feature = &mut features[indices[n]];
+            completed += 1;
+
+            let error = match result {
+                Ok(enrichment) => {
+                    apply_enrichment(feature, enrichment);
+                    enriched_count += 1;
+                    None
+                }
+                Err(e) => {
+                    log::warn!("Failed to enrich feature #{}: {}", feature.cluster_id, e);
+                    Some(e.to_string())
+                }
+            };
+
+            let usage = client.usage();
+            on_progress(EnrichmentProgress {
+                cluster_id: feature.cluster_id,
+                status: if error.is_none() { "enriched" } else { "failed" }.to_string(),
+                title: feature.title.clone(),
+                error,
+                completed,
+                total,
+                cache_savings_usd: pricing.cache_savings(client.model(), &usage),
+                usage,
+            });
+        },
+    )
+    .await?;
+
+    Ok(enriched_count)
+}
+
+/// Extract the structured intent of one prompt.
+pub async fn extract_intent(
+    client: &dyn LlmProvider,
+    prompt_text: &str,
+) -> Result<PromptIntent, ClaudeError> {
+    let input = client
+        .call_tool(
+            &[SystemBlock::text(INTENT_EXTRACTION_SYSTEM)],
+            prompt_text,
+            1024,
+            prompt_intent_tool(),
+        )
+        .await?;
+    serde_json::from_value(input)
+        .map_err(|e| ClaudeError::Parse(format!("Invalid intent tool input: {}", e)))
+}
+
+fn intent_cache_key(model: &str, prompt_text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0]);
+    hasher.update(INTENT_EXTRACTION_PROMPT_VERSION.to_le_bytes());
+    hasher.update(prompt_text.as_bytes());
+    format!("intent:{}", hex::encode(hasher.finalize()))
+}
+
+/// Cache keys of the prompts at `targets`, by index into `sessions`.
+pub fn intent_cache_keys(
+    model: &str,
+    sessions: &[PromptSession],
+    targets: &[usize],
+) -> HashMap<usize, String> {
+    targets
+        .iter()
+        .map(|&i| (i, intent_cache_key(model, &sessions[i].prompt_text)))
+        .collect()
+}
+
+fn apply_intent(session: &mut PromptSession, intent: PromptIntent) {
+    session.intent = Some(intent.goal.clone());
+    session.intent_details = Some(intent);
+}
+
+/// Fill prompts from cached intents. Returns the indices restored.
+pub fn apply_cached_intents(
+    db: &Database,
+    sessions: &mut [PromptSession],
+    keys: &HashMap<usize, String>,
+) -> HashSet<usize> {
+    let mut restored = HashSet::new();
+    for (&i, key) in keys {
+        let Some(intent) = db
+            .cache_get(key)
+            .and_then(|value| serde_json::from_str::<PromptIntent>(&value).ok())
+        else {
             continue;
-        }
+        };
+        apply_intent(&mut sessions[i], intent);
+        restored.insert(i);
+    }
+    restored
+}
+
+/// Cache the intents of keyed prompts that now have one, skipping those that
+/// came from the cache, then evict stale entries.
+pub fn store_intents(
+    db: &Database,
+    sessions: &[PromptSession],
+    keys: &HashMap<usize, String>,
+    skip: &HashSet<usize>,
+) -> Result<(), String> {
+    for (&i, key) in keys.iter().filter(|(i, _)| !skip.contains(i)) {
+        let Some(intent) = &sessions[i].intent_details else {
+            continue;
+        };
+        let value = serde_json::to_string(intent)
+            .map_err(|e| format!("Failed to serialize intent: {}", e))?;
+        db.cache_set(key, &value, Some(ENRICHMENT_CACHE_TTL))?;
+    }
+    db.cache_evict(MAX_CACHE_ENTRIES)?;
+    Ok(())
+}
+
+/// Extract the intents of the prompts at `targets`, running up to
+/// `max_concurrent` requests at once. Returns the number extracted.
+pub async fn extract_intents_batch(
+    client: Arc<dyn LlmProvider>,
+    sessions: &mut [PromptSession],
+    targets: &[usize],
+    max_concurrent: usize,
+    on_progress: impl Fn(IntentProgress),
+) -> Result<u32, String> {
+    let prompts: Vec<String> = targets
+        .iter()
+        .map(|&i| sessions[i].prompt_text.clone())
+        .collect();
+
+    let total = prompts.len() as u32;
+    let mut completed: u32 = 0;
+    let mut extracted: u32 = 0;
+
+    run_concurrently(
+        prompts,
+        max_concurrent,
+        |prompt_text| {
+            let client = Arc::clone(&client);
+            async move { extract_intent(&*client, &prompt_text).await }
+        },
+        |n, result| {
+            let session = &mut sessions[targets[n]];
+            completed += 1;
+
+            let error = match result {
+                Ok(intent) => {
+                    apply_intent(session, intent);
+                    extracted += 1;
+                    None
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Failed to extract intent of prompt at {} in session {}: {}",
+                        session.timestamp,
+                        session.session_id,
+                        e
+                    );
+                    Some(e.to_string())
+                }
+            };
 
-        let _permit = semaphore
-            .acquire()
-            .await
-            .map_err(|e| format!("Semaphore error: {}", e))?;
+            on_progress(IntentProgress {
+                session_id: session.session_id.clone(),
+                timestamp: session.timestamp.clone(),
+                status: if error.is_none() { "extracted" } else { "failed" }.to_string(),
+                goal: session.intent.clone(),
+                error,
+                completed,
+                total,
+            });
+        },
+    )
+    .await?;
 
-        // Build commit details string
-        let mut details = String::new();
-        for hash in &feature.commit_hashes {
-            if let Some(subject) = commit_subjects.get(hash) {
-                details.push_str(&format!("- {} {}\n", &hash[..7.min(hash.len())], subject));
+    Ok(extracted)
+}
+
+/// Copy prompt intents onto the sub-features built from those prompts, and set
+/// each feature's intent from its sub-features' goals, in prompt order.
+pub fn derive_feature_intents(features: &mut [FeatureCluster], sessions: &[PromptSession]) {
+    let goals: HashMap<(&str, &str), &str> = sessions
+        .iter()
+        .filter_map(|s| {
+            s.intent
+                .as_deref()
+                .map(|goal| ((s.session_id.as_str(), s.timestamp.as_str()), goal))
+        })
+        .collect();
+
+    for feature in features.iter_mut() {
+        for sub in feature.sub_features.iter_mut() {
+            if let Some(goal) = goals.get(&(sub.session_id.as_str(), sub.timestamp.as_str())) {
+                sub.intent = Some(goal.to_string());
             }
         }
 
-        match enrich_feature(client, feature, &details).await {
-            Ok(enrichment) => {
-                feature.title = Some(enrichment.title);
-                feature.narrative = Some(enrichment.narrative);
-                feature.key_decisions = enrichment.key_decisions;
-                enriched_count += 1;
-            }
-            Err(e) => {
-                log::warn!(
-                    "Failed to enrich feature #{}: {}",
-                    feature.cluster_id,
-                    e
-                );
+        let mut feature_goals: Vec<&str> = Vec::new();
+        for goal in feature.sub_features.iter().filter_map(|s| s.intent.as_deref()) {
+            if !feature_goals.contains(&goal) {
+                feature_goals.push(goal);
             }
         }
+        if !feature_goals.is_empty() {
+            feature.intent = Some(feature_goals.join(" "));
+        }
     }
+}
 
-    Ok(enriched_count)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_enrichment_from_forced_tool_call() {
+        let response: MessageResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_01",
+            "stop_reason": "tool_use",
+            "usage": { "input_tokens": 812, "output_tokens": 240 },
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_01",
+                "name": FEATURE_ENRICHMENT_TOOL,
+                "input": {
+                    "title": "Session bundle export and import",
+                    "narrative": "Adds gzipped bundles of redacted transcripts.",
+                    "key_decisions": ["Redact per JSON line"],
+                    "significant_changes": [
+                        { "function": "export_sessions", "description": "Writes the bundle" }
+                    ]
+                }
+            }]
+        }))
+        .unwrap();
+
+        let input = response.tool_input(FEATURE_ENRICHMENT_TOOL).unwrap().clone();
+        let enrichment = parse_enrichment(input).unwrap();
+        assert_eq!(enrichment.title, "Session bundle export and import");
+        assert_eq!(enrichment.significant_changes[0].function, "export_sessions");
+
+        let missing_changes = serde_json::json!({
+            "title": "t", "narrative": "n", "key_decisions": []
+        });
+        assert!(matches!(
+            parse_enrichment(missing_changes),
+            Err(ClaudeError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn only_the_repo_preamble_is_marked_for_caching() {
+        let system =
+            serde_json::to_value(feature_system(FEATURE_NARRATIVE_SYSTEM, "Repository: x")).unwrap();
+        assert!(system[0].get("cache_control").is_none());
+        assert_eq!(system[1]["cache_control"]["type"], "ephemeral");
+        assert_eq!(system[1]["text"], "Repository: x");
+    }
+
+    #[test]
+    fn cache_savings_credit_reads_and_charge_writes() {
+        let usage: crate::claude::types::Usage = serde_json::from_value(serde_json::json!({
+            "input_tokens": 120,
+            "output_tokens": 300,
+            "cache_read_input_tokens": 2_000_000
+        }))
+        .unwrap();
+        assert_eq!(usage.cache_creation_input_tokens, 0);
+        let totals = crate::types::TokenUsage {
+            cache_read_tokens: usage.cache_read_input_tokens as u64,
+            cache_creation_tokens: 1_000_000,
+            ..Default::default()
+        };
+        // Sonnet: reads save $2.70/MTok, writes cost an extra $0.75/MTok
+        let saved = PricingTable::default().cache_savings("claude-sonnet-4-5", &totals);
+        assert!((saved - (2.0 * 2.70 - 0.75)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recognizes_conventional_commit_subjects() {
+        assert!(is_conventional_subject("feat(parser): accept tabs"));
+        assert!(is_conventional_subject("fix!: drop legacy flag"));
+        assert!(!is_conventional_subject("Fix: capitalised"));
+        assert!(!is_conventional_subject("Merge branch 'main'"));
+    }
+
+    fn feature(cluster_id: i32, auto_label: &str) -> FeatureCluster {
+        FeatureCluster {
+            cluster_id,
+            auto_label: auto_label.to_string(),
+            commit_hashes: vec![format!("{:07}", cluster_id)],
+            time_start: "2026-01-01T00:00:00Z".to_string(),
+            time_end: "2026-01-02T00:00:00Z".to_string(),
+            total_lines_added: 10,
+            total_lines_removed: 2,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn enriches_features_through_any_provider() {
+        let provider = Arc::new(
+            crate::llm::mock::MockProvider::new()
+                .with_tool_input(
+                    FEATURE_ENRICHMENT_TOOL,
+                    serde_json::json!({
+                        "title": "Offline enrichment",
+                        "narrative": "Runs against a local model.",
+                        "key_decisions": [],
+                        "significant_changes": []
+                    }),
+                )
+                .with_text("A streamed narrative.")
+                .failing_on("flaky"),
+        );
+        let mut features = vec![feature(1, "provider"), feature(2, "flaky"), feature(3, "skipped")];
+        let progress = std::sync::Mutex::new(Vec::new());
+
+        let enriched = enrich_features_batch(
+            provider.clone(),
+            "Repository: x",
+            &mut features,
+            &HashSet::from([1, 2]),
+            &HashMap::new(),
+            2,
+            |p| progress.lock().unwrap().push((p.cluster_id, p.status)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(enriched, 1);
+        assert_eq!(features[0].title.as_deref(), Some("Offline enrichment"));
+        assert!(features[1].title.is_none() && features[2].title.is_none());
+        let mut progress = progress.into_inner().unwrap();
+        progress.sort();
+        assert_eq!(progress, [(1, "enriched".to_string()), (2, "failed".to_string())]);
+        assert_eq!(provider.messages().len(), 2);
+        assert!(provider.usage().input_tokens > 0);
+
+        let mut pieces = Vec::new();
+        let narrative = stream_narrative(&*provider, "", &features[0], &HashMap::new(), |t| {
+            pieces.push(t.to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(narrative, "A streamed narrative.");
+        assert_eq!(pieces.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn enriches_features_against_the_fake_api() {
+        use crate::claude::fake_server::{CannedResponse, FakeServer};
+
+        let server = FakeServer::start().await;
+        let enrichment = serde_json::json!({
+            "title": "Fake API enrichment",
+            "narrative": "Served by the fake Messages API.",
+            "key_decisions": ["Close every connection"],
+            "significant_changes": []
+        });
+        server.route(
+            "Auto-label: throttled",
+            vec![
+                CannedResponse::rate_limited(0),
+                CannedResponse::tool_use(FEATURE_ENRICHMENT_TOOL, enrichment.clone()),
+            ],
+        );
+        server.route(
+            "Auto-label: rejected",
+            vec![CannedResponse::error(400, "invalid_request_error", "Bad feature")],
+        );
+        server.route(
+            "Auto-label: plain",
+            vec![CannedResponse::tool_use(FEATURE_ENRICHMENT_TOOL, enrichment)],
+        );
+
+        let settings = crate::types::ApiSettings {
+            base_url: format!("{}/", server.base_url()),
+            headers: HashMap::from([("x-gateway-team".to_string(), "codelens".to_string())]),
+            ..Default::default()
+        };
+        let client = ClaudeClient::new("sk-test".to_string(), "claude-test".to_string(), &settings)
+            .unwrap();
+        let mut features = vec![
+            feature(1, "throttled"),
+            feature(2, "rejected"),
+            feature(3, "plain"),
+        ];
+
+        let enriched = enrich_features_batch(
+            Arc::new(client),
+            "Repository: x",
+            &mut features,
+            &HashSet::from([1, 2, 3]),
+            &HashMap::new(),
+            3,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(enriched, 2);
+        assert_eq!(features[0].title.as_deref(), Some("Fake API enrichment"));
+        assert!(features[1].title.is_none());
+        assert_eq!(features[2].key_decisions, ["Close every connection"]);
+
+        // The 429 is retried once; the 400 is not
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests.iter().all(|r| r.method == "POST" && r.path == "/v1/messages"));
+        assert_eq!(requests[0].headers["x-api-key"], "sk-test");
+        assert_eq!(requests[0].headers["x-gateway-team"], "codelens");
+
+        // An auth error stops the run
+        let denied = FakeServer::start().await;
+        denied.route(
+            "/v1/messages",
+            vec![CannedResponse::error(401, "authentication_error", "invalid x-api-key")],
+        );
+        let settings = crate::types::ApiSettings {
+            base_url: denied.base_url().to_string(),
+            ..Default::default()
+        };
+        let client =
+            ClaudeClient::new("sk-bad".to_string(), "claude-test".to_string(), &settings).unwrap();
+        let result = enrich_features_batch(
+            Arc::new(client),
+            "",
+            &mut features,
+            &HashSet::from([2]),
+            &HashMap::new(),
+            1,
+            |_| {},
+        )
+        .await;
+        assert!(result.unwrap_err().contains("invalid x-api-key"));
+    }
 }
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::claude::pricing::PricingTable;
+use crate::types::{Analytics, FeatureCluster, ProjectData, PromptSession, WeekVelocity};
+
+/// Price every prompt session with the pricing table and roll the cost up into
+/// sub-features, features and weekly analytics.
+pub fn apply_costs(project: &mut ProjectData, pricing: &PricingTable) {
+    // (session_id, timestamp) → (cost, number of features the prompt contributed to)
+    let mut prompt_costs: HashMap<(String, String), (f64, usize)> = HashMap::new();
+
+    for session in project.prompt_sessions.iter_mut() {
+        session.cost_usd = pricing.cost(session.model.as_deref(), &session.token_usage);
+        prompt_costs.insert(
+            (session.session_id.clone(), session.timestamp.clone()),
+            (session.cost_usd, session.associated_feature_ids.len().max(1)),
+        );
+    }
+
+    // A prompt that fed several features has its cost split evenly between them
+    for feature in project.features.iter_mut() {
+        for sub in feature.sub_features.iter_mut() {
+            sub.cost_usd = prompt_costs
+                .get(&(sub.session_id.clone(), sub.timestamp.clone()))
+                .map(|(cost, shares)| cost / *shares as f64)
+                .unwrap_or(0.0);
+        }
+        feature.cost_usd = feature.sub_features.iter().map(|s| s.cost_usd).sum();
+    }
+
+    summarize_costs(
+        &mut project.analytics,
+        &project.prompt_sessions,
+        &project.features,
+    );
+    project.analytics.pricing_version = pricing.version.clone();
+}
+
+/// Fill the cost fields of `Analytics` from already-priced prompts and features.
+pub fn summarize_costs(
+    analytics: &mut Analytics,
+    sessions: &[PromptSession],
+    features: &[FeatureCluster],
+) {
+    analytics.total_cost_usd = sessions.iter().map(|s| s.cost_usd).sum();
+
+    // "Cost per shipped feature" only counts features that prompts contributed to
+    let costed: Vec<f64> = features
+        .iter()
+        .map(|f| f.cost_usd)
+        .filter(|c| *c > 0.0)
+        .collect();
+    analytics.avg_cost_per_feature = if costed.is_empty() {
+        0.0
+    } else {
+        costed.iter().sum::<f64>() / costed.len() as f64
+    };
+
+    let mut week_costs: HashMap<String, f64> = HashMap::new();
+    for session in sessions {
+        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&session.timestamp) {
+            let week = dt.format("%G-W%V").to_string();
+            *week_costs.entry(week).or_insert(0.0) += session.cost_usd;
+        }
+    }
+
+    for velocity in analytics.velocity_by_week.iter_mut() {
+        velocity.cost_usd = week_costs.remove(&velocity.week).unwrap_or(0.0);
+    }
+    // Weeks with prompts but no commits still show up in the cost timeline
+    for (week, cost_usd) in week_costs {
+        analytics.velocity_by_week.push(WeekVelocity {
+            week,
+            features: 0,
+            commits: 0,
+            cost_usd,
+        });
+    }
+    analytics
+        .velocity_by_week
+        .sort_by(|a, b| a.week.cmp(&b.week));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::pricing::PRICING_VERSION;
+    use crate::types::{SubFeature, TokenUsage};
+
+    fn prompt(
+        timestamp: &str,
+        model: &str,
+        input: u64,
+        output: u64,
+        features: &[i32],
+    ) -> PromptSession {
+        PromptSession {
+            session_id: "s1".to_string(),
+            timestamp: timestamp.to_string(),
+            model: Some(model.to_string()),
+            token_usage: TokenUsage {
+                input_tokens: input,
+                output_tokens: output,
+                ..Default::default()
+            },
+            associated_feature_ids: features.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn feature(cluster_id: i32, prompt_timestamps: &[&str]) -> FeatureCluster {
+        FeatureCluster {
+            cluster_id,
+            sub_features: prompt_timestamps
+                .iter()
+                .map(|t| SubFeature {
+                    session_id: "s1".to_string(),
+                    timestamp: t.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn splits_prompt_costs_across_features_and_weeks() {
+        let first = "2026-03-02T10:00:00Z";
+        let second = "2026-03-10T10:00:00Z";
+        let mut project = ProjectData {
+            prompt_sessions: vec![
+                // $3, shared by features 1 and 2
+                prompt(first, "claude-sonnet-4-5-20250929", 1_000_000, 0, &[1, 2]),
+                // $5, feature 2 only, in a week without commits
+                prompt(second, "claude-haiku-4-5", 0, 1_000_000, &[2]),
+            ],
+            features: vec![feature(1, &[first]), feature(2, &[first, second]), feature(3, &[])],
+            ..Default::default()
+        };
+        project.analytics.velocity_by_week = vec![WeekVelocity {
+            week: "2026-W10".to_string(),
+            features: 2,
+            commits: 3,
+            cost_usd: 0.0,
+        }];
+
+        apply_costs(&mut project, &PricingTable::default());
+
+        let costs: Vec<f64> = project.prompt_sessions.iter().map(|s| s.cost_usd).collect();
+        assert_eq!(costs, [3.0, 5.0]);
+        let feature_costs: Vec<f64> = project.features.iter().map(|f| f.cost_usd).collect();
+        assert_eq!(feature_costs, [1.5, 6.5, 0.0]);
+        assert_eq!(project.features[1].sub_features[1].cost_usd, 5.0);
+
+        let analytics = &project.analytics;
+        assert_eq!(analytics.total_cost_usd, 8.0);
+        assert_eq!(analytics.avg_cost_per_feature, 4.0);
+        assert_eq!(analytics.pricing_version, PRICING_VERSION);
+        let weeks: Vec<(&str, u32, f64)> = analytics
+            .velocity_by_week
+            .iter()
+            .map(|w| (w.week.as_str(), w.commits, w.cost_usd))
+            .collect();
+        assert_eq!(weeks, [("2026-W10", 3, 3.0), ("2026-W11", 0, 5.0)]);
+    }
+}
@@ -1,66 +1,346 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tauri::{AppHandle, Emitter};
 
 use crate::claude::client::ClaudeClient;
-use crate::types::{ProjectData, ScanProgress};
+use crate::llm::openai::OpenAiCompatibleProvider;
+use crate::llm::LlmProvider;
+use crate::storage::db::Database;
+use crate::types::{AppSettings, FeatureCluster, NarrativeDelta, ProjectData};
 
-use super::claude_api::enrich_features_batch;
+use super::claude_api::{
+    apply_cached_enrichments, apply_cached_intents, derive_feature_intents,
+    enrich_features_batch, enrichment_cache_keys, extract_intents_batch, intent_cache_keys,
+    repo_preamble, store_enrichments, store_intents, stream_narrative,
+};
+use super::intent::refresh_intent_summaries;
+use super::settings::resolved_settings;
 
-/// Enrich features in a ProjectData with Claude API-generated titles, narratives,
-/// and key decisions. Requires ANTHROPIC_API_KEY environment variable.
-#[tauri::command]
-pub async fn enrich_features(project_id: String) -> Result<ScanProgress, String> {
-    // Check for API key
-    let api_key = std::env::var("ANTHROPIC_API_KEY").map_err(|_| {
-        "ANTHROPIC_API_KEY not set. Set it to enable Claude API enrichment.".to_string()
-    })?;
-
-    let model =
-        std::env::var("CLAUDE_MODEL").unwrap_or_else(|_| "claude-sonnet-4-5-20250929".to_string());
-
-    let _client = ClaudeClient::new(api_key, model);
-
-    // TODO: Load project data from storage by project_id
-    // For now, return a pending status indicating enrichment is ready
-    Ok(ScanProgress {
-        stage: "enrichment_ready".to_string(),
-        progress: 0.0,
-        message: format!(
-            "Enrichment ready for project: {}. Call scan_repository with enrichment enabled.",
-            project_id
-        ),
-    })
-}
+/// Event carrying an `EnrichmentProgress` for each finished feature.
+pub const ENRICHMENT_PROGRESS_EVENT: &str = "enrichment-progress";
+/// Event carrying an `IntentProgress` for each finished prompt.
+pub const INTENT_PROGRESS_EVENT: &str = "intent-progress";
+/// Event carrying a `NarrativeDelta` for each piece of a streamed narrative.
+pub const NARRATIVE_DELTA_EVENT: &str = "narrative-delta";
 
-/// Enrich features within a scan pipeline. Called by scan_repository when
-/// Claude API key is available.
-pub async fn enrich_project_features(project: &mut ProjectData) -> Result<u32, String> {
-    let api_key = match std::env::var("ANTHROPIC_API_KEY") {
-        Ok(key) if !key.is_empty() => key,
-        _ => return Ok(0), // No API key — skip enrichment silently
-    };
+/// A Claude client with the model and API connection of `settings`.
+pub fn claude_client(settings: &AppSettings) -> Result<ClaudeClient, String> {
+    ClaudeClient::new(api_key(settings)?, settings.claude_model.clone(), &settings.api)
+}
 
-    let model =
-        std::env::var("CLAUDE_MODEL").unwrap_or_else(|_| "claude-sonnet-4-5-20250929".to_string());
+pub fn api_key(settings: &AppSettings) -> Result<String, String> {
+    match &settings.api_key {
+        Some(key) if !key.is_empty() => Ok(key.clone()),
+        _ => Err("No API key. Save one in the settings or set ANTHROPIC_API_KEY.".to_string()),
+    }
+}
 
-    let max_concurrent: usize = std::env::var("CLAUDE_MAX_CONCURRENT")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(3);
+/// Model of the configured provider, which cache keys are built from.
+pub fn llm_model(settings: &AppSettings) -> String {
+    match settings.provider.as_str() {
+        "openai" => settings.openai_model.clone().unwrap_or_default(),
+        _ => settings.claude_model.clone(),
+    }
+}
 
-    let client = ClaudeClient::new(api_key, model);
+/// The provider enrichment runs on: Claude, or the OpenAI-compatible server
+/// at `openai_base_url` running `openai_model`.
+pub fn llm_provider(settings: &AppSettings) -> Result<Arc<dyn LlmProvider>, String> {
+    match settings.provider.as_str() {
+        "anthropic" => Ok(Arc::new(claude_client(settings)?)),
+        "openai" => {
+            let provider = OpenAiCompatibleProvider::new(
+                &settings.openai_base_url,
+                settings.openai_api_key.clone(),
+                llm_model(settings),
+                &settings.api,
+            )?;
+            Ok(Arc::new(provider))
+        }
+        other => Err(format!(
+            "Unknown provider \"{}\": expected \"anthropic\" or \"openai\"",
+            other
+        )),
+    }
+}
 
-    // Build commit subject lookup
-    let commit_subjects: HashMap<String, String> = project
+pub fn commit_subjects(project: &ProjectData) -> HashMap<String, String> {
+    project
         .commits
         .iter()
         .map(|c| (c.hash.clone(), c.subject.clone()))
+        .collect()
+}
+
+/// Features to enrich: `feature_ids` when given, otherwise every feature
+/// without a title.
+pub fn enrichment_targets(
+    project: &ProjectData,
+    feature_ids: Option<Vec<i32>>,
+) -> Result<HashSet<i32>, String> {
+    match feature_ids {
+        Some(ids) => {
+            if let Some(missing) = ids
+                .iter()
+                .find(|id| !project.features.iter().any(|f| f.cluster_id == **id))
+            {
+                return Err(format!("Feature not found: {}", missing));
+            }
+            Ok(ids.into_iter().collect())
+        }
+        None => Ok(project
+            .features
+            .iter()
+            .filter(|f| f.title.is_none())
+            .map(|f| f.cluster_id)
+            .collect()),
+    }
+}
+
+/// Fill unenriched features and prompts without an intent from the cache.
+/// Used by scans, which don't call the model themselves.
+pub fn restore_cached_enrichments(project: &mut ProjectData, settings: &AppSettings) {
+    let db = match Database::open_default() {
+        Ok(db) => db,
+        Err(e) => {
+            log::warn!("Enrichment cache unavailable: {}", e);
+            return;
+        }
+    };
+    let model = llm_model(settings);
+
+    let features: HashSet<i32> = project
+        .features
+        .iter()
+        .filter(|f| f.title.is_none())
+        .map(|f| f.cluster_id)
+        .collect();
+    let keys = enrichment_cache_keys(
+        &model,
+        &project.features,
+        &commit_subjects(project),
+        &features,
+    );
+    let restored = apply_cached_enrichments(&db, &mut project.features, &keys);
+
+    let prompts: Vec<usize> = (0..project.prompt_sessions.len())
+        .filter(|&i| project.prompt_sessions[i].intent_details.is_none())
         .collect();
+    let keys = intent_cache_keys(&model, &project.prompt_sessions, &prompts);
+    let restored_intents = apply_cached_intents(&db, &mut project.prompt_sessions, &keys);
+    derive_feature_intents(&mut project.features, &project.prompt_sessions);
+
+    if !restored.is_empty() || !restored_intents.is_empty() {
+        log::info!(
+            "Reused {} cached feature enrichments and {} prompt intents",
+            restored.len(),
+            restored_intents.len()
+        );
+    }
+}
+
+/// Tauri command: enrich a stored project's features with Claude-generated
+/// titles, narratives, key decisions and significant changes, then save it.
+///
+/// Enriches `feature_ids` when given, otherwise every feature without a title.
+/// Cached enrichments are reused unless `force` is set. Emits an
+/// `enrichment-progress` event per feature. Runs on the `LLM_PROVIDER`.
+#[tauri::command]
+pub async fn enrich_features(
+    app: AppHandle,
+    project_id: String,
+    feature_ids: Option<Vec<i32>>,
+    force: Option<bool>,
+) -> Result<ProjectData, String> {
+    let settings = resolved_settings()?;
+    let client = llm_provider(&settings)?;
+    let mut project = Database::open_default()?.load_project(&project_id)?;
+
+    let targets = enrichment_targets(&project, feature_ids)?;
+
+    let result = enrich_project_features(
+        &app,
+        client,
+        &mut project,
+        &targets,
+        force.unwrap_or(false),
+        settings.max_concurrent_api_calls as usize,
+    )
+    .await;
+
+    // Keep whatever was enriched before a batch-stopping error
+    Database::open_default()?.update_project(&project_id, &project)?;
+    let count = result?;
+    log::info!("Enriched {} features of project {}", count, project_id);
+
+    Ok(project)
+}
+
+/// Enrich the `targets` features of a project, reusing cached results unless
+/// `force` is set and caching the new ones. Returns the number of features
+/// enriched through the API.
+async fn enrich_project_features(
+    app: &AppHandle,
+    client: Arc<dyn LlmProvider>,
+    project: &mut ProjectData,
+    targets: &HashSet<i32>,
+    force: bool,
+    max_concurrent: usize,
+) -> Result<u32, String> {
+    let commit_subjects = commit_subjects(project);
+    let keys = enrichment_cache_keys(client.model(), &project.features, &commit_subjects, targets);
+    let restored = if force {
+        HashSet::new()
+    } else {
+        match Database::open_default() {
+            Ok(db) => apply_cached_enrichments(&db, &mut project.features, &keys),
+            Err(e) => {
+                log::warn!("Enrichment cache unavailable: {}", e);
+                HashSet::new()
+            }
+        }
+    };
+    let pending: HashSet<i32> = targets.difference(&restored).copied().collect();
 
-    enrich_features_batch(
-        &client,
+    let preamble = repo_preamble(project);
+    let result = enrich_features_batch(
+        Arc::clone(&client),
+        &preamble,
         &mut project.features,
+        &pending,
         &commit_subjects,
         max_concurrent,
+        |progress| {
+            if let Err(e) = app.emit(ENRICHMENT_PROGRESS_EVENT, progress) {
+                log::warn!("Failed to emit enrichment progress: {}", e);
+            }
+        },
+    )
+    .await;
+
+    let usage = client.usage();
+    log::info!(
+        "Enrichment used {} input, {} cache write, {} cache read and {} output tokens",
+        usage.input_tokens,
+        usage.cache_creation_tokens,
+        usage.cache_read_tokens,
+        usage.output_tokens
+    );
+
+    if let Err(e) = Database::open_default()
+        .and_then(|db| store_enrichments(&db, &project.features, &keys, &restored))
+    {
+        log::warn!("Failed to cache feature enrichments: {}", e);
+    }
+
+    result
+}
+
+/// Tauri command: rewrite one feature's narrative with the `LLM_PROVIDER`,
+/// streaming it to the frontend as `narrative-delta` events, then save the
+/// project and return the updated feature.
+#[tauri::command]
+pub async fn stream_feature_narrative(
+    app: AppHandle,
+    project_id: String,
+    feature_id: i32,
+) -> Result<FeatureCluster, String> {
+    let client = llm_provider(&resolved_settings()?)?;
+    let mut project = Database::open_default()?.load_project(&project_id)?;
+    let commit_subjects = commit_subjects(&project);
+    let index = project
+        .features
+        .iter()
+        .position(|f| f.cluster_id == feature_id)
+        .ok_or_else(|| format!("Feature not found: {}", feature_id))?;
+
+    let narrative = stream_narrative(
+        &*client,
+        &repo_preamble(&project),
+        &project.features[index],
+        &commit_subjects,
+        |text| {
+            let delta = NarrativeDelta {
+                cluster_id: feature_id,
+                text: text.to_string(),
+            };
+            if let Err(e) = app.emit(NARRATIVE_DELTA_EVENT, delta) {
+                log::warn!("Failed to emit narrative delta: {}", e);
+            }
+        },
     )
-    .await
+    .await?;
+    // Not cached: the enrichment cache holds replies to the tool prompt, and a
+    // streamed narrative comes from a different one
+    project.features[index].narrative = Some(narrative);
+
+    Database::open_default()?.update_project(&project_id, &project)?;
+    Ok(project.features.swap_remove(index))
+}
+
+/// Tauri command: extract the goal, requirements, constraints and context of a
+/// stored project's prompts with the `LLM_PROVIDER`, then save the project.
+///
+/// Covers prompts without an extracted intent, or all prompts with `force`,
+/// which also ignores cached results. Feature intents are rebuilt from their
+/// prompts' goals. Emits an `intent-progress` event per prompt.
+#[tauri::command]
+pub async fn extract_prompt_intents(
+    app: AppHandle,
+    project_id: String,
+    force: Option<bool>,
+) -> Result<ProjectData, String> {
+    let settings = resolved_settings()?;
+    let client = llm_provider(&settings)?;
+    let force = force.unwrap_or(false);
+    let mut project = Database::open_default()?.load_project(&project_id)?;
+
+    let targets: Vec<usize> = (0..project.prompt_sessions.len())
+        .filter(|&i| force || project.prompt_sessions[i].intent_details.is_none())
+        .collect();
+    let keys = intent_cache_keys(client.model(), &project.prompt_sessions, &targets);
+    let restored = if force {
+        HashSet::new()
+    } else {
+        match Database::open_default() {
+            Ok(db) => apply_cached_intents(&db, &mut project.prompt_sessions, &keys),
+            Err(e) => {
+                log::warn!("Enrichment cache unavailable: {}", e);
+                HashSet::new()
+            }
+        }
+    };
+    let pending: Vec<usize> = targets
+        .into_iter()
+        .filter(|i| !restored.contains(i))
+        .collect();
+
+    let result = extract_intents_batch(
+        client,
+        &mut project.prompt_sessions,
+        &pending,
+        settings.max_concurrent_api_calls as usize,
+        |progress| {
+            if let Err(e) = app.emit(INTENT_PROGRESS_EVENT, progress) {
+                log::warn!("Failed to emit intent progress: {}", e);
+            }
+        },
+    )
+    .await;
+
+    if let Err(e) = Database::open_default()
+        .and_then(|db| store_intents(&db, &project.prompt_sessions, &keys, &restored))
+    {
+        log::warn!("Failed to cache prompt intents: {}", e);
+    }
+    derive_feature_intents(&mut project.features, &project.prompt_sessions);
+    refresh_intent_summaries(&mut project.intent_analyses, &project.prompt_sessions);
+
+    // Keep whatever was extracted before a batch-stopping error
+    Database::open_default()?.update_project(&project_id, &project)?;
+    let count = result?;
+    log::info!("Extracted {} prompt intents of project {}", count, project_id);
+
+    Ok(project)
 }
@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+
+use crate::types::{Analytics, CommitData, IntentAnalysis, ProjectData, PromptSession};
+
+/// Word-set overlap above which a prompt counts as a re-prompt of the one before it.
+const REPROMPT_SIMILARITY: f32 = 0.4;
+/// Prompts with fewer distinct words ("yes", "continue") are never re-prompts.
+const MIN_REPROMPT_WORDS: usize = 3;
+/// Later fix commits on a prompt's files within this window count as rework.
+const REWORK_WINDOW_DAYS: i64 = 7;
+
+/// File-like references in prompt text: a path or file name with an extension.
+fn file_reference_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?:[A-Za-z0-9_.\-]+/)*[A-Za-z0-9_\-]+\.(?:rs|tsx?|jsx?|py|mojo|go|java|css|html|json|toml|ya?ml|md|sql)\b",
+        )
+        .expect("file reference pattern is valid")
+    })
+}
+
+/// Files a prompt mentions, in order of first mention.
+fn files_mentioned(prompt: &str) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for m in file_reference_regex().find_iter(prompt) {
+        let file = m.as_str().trim_start_matches("./").to_string();
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    files
+}
+
+/// Whether two paths name the same file: one is a suffix of the other at a
+/// path boundary. Session paths are absolute, commit and prompt paths relative.
+fn same_file(a: &str, b: &str) -> bool {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    long.ends_with(short)
+        && (long.len() == short.len() || long[..long.len() - short.len()].ends_with('/'))
+}
+
+fn words(prompt: &str) -> HashSet<String> {
+    prompt
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Jaccard similarity of the prompts' word sets.
+fn lexical_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    if a.len() < MIN_REPROMPT_WORDS || b.len() < MIN_REPROMPT_WORDS {
+        return 0.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f32 / (a.len() + b.len() - shared) as f32
+}
+
+fn parse_time(ts: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(ts)
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// A commit that fixes or reverts earlier work.
+fn is_rework_commit(commit: &CommitData) -> bool {
+    let subject = commit.subject.to_lowercase();
+    commit.change_type == "bug_fix" || subject.starts_with("revert") || subject.starts_with("fix")
+}
+
+/// Later fix commits touching the files a prompt wrote, outside its own commits.
+fn rework_commits<'a>(session: &PromptSession, commits: &'a [CommitData]) -> Vec<&'a CommitData> {
+    let Some(end) = session
+        .time_end
+        .as_deref()
+        .and_then(parse_time)
+        .or_else(|| parse_time(&session.timestamp))
+    else {
+        return vec![];
+    };
+    let window_end = end + Duration::days(REWORK_WINDOW_DAYS);
+
+    commits
+        .iter()
+        .filter(|c| !session.associated_commit_hashes.contains(&c.hash))
+        .filter(|c| parse_time(&c.timestamp).is_some_and(|t| t > end && t <= window_end))
+        .filter(|c| is_rework_commit(c))
+        .filter(|c| {
+            c.files_changed.iter().any(|f| {
+                session
+                    .files_written
+                    .iter()
+                    .any(|written| same_file(written, &f.path))
+            })
+        })
+        .collect()
+}
+
+/// Number of re-prompts following each prompt: consecutive prompts in the same
+/// session that restate the one before them. Sessions must be in order.
+fn reprompt_counts(sessions: &[PromptSession], order: &[usize]) -> HashMap<usize, u32> {
+    let mut counts: HashMap<usize, u32> = HashMap::new();
+    let word_sets: Vec<HashSet<String>> = order
+        .iter()
+        .map(|&i| words(&sessions[i].prompt_text))
+        .collect();
+
+    for start in 0..order.len() {
+        let mut count = 0u32;
+        let mut j = start + 1;
+        while j < order.len()
+            && lexical_similarity(&word_sets[j - 1], &word_sets[j]) >= REPROMPT_SIMILARITY
+        {
+            count += 1;
+            j += 1;
+        }
+        counts.insert(order[start], count);
+    }
+    counts
+}
+
+/// Analyze what each prompt asked for against what happened: files mentioned
+/// vs written, re-prompts in the same session, and rework in later commits.
+pub fn analyze_intents(sessions: &[PromptSession], commits: &[CommitData]) -> Vec<IntentAnalysis> {
+    // Prompts of each session in time order
+    let mut by_session: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, session) in sessions.iter().enumerate() {
+        by_session.entry(&session.session_id).or_default().push(i);
+    }
+    let mut reprompts: HashMap<usize, u32> = HashMap::new();
+    for order in by_session.values_mut() {
+        order.sort_by(|&a, &b| sessions[a].timestamp.cmp(&sessions[b].timestamp));
+        reprompts.extend(reprompt_counts(sessions, order));
+    }
+
+    sessions
+        .iter()
+        .enumerate()
+        .map(|(i, session)| {
+            let files_intended = files_mentioned(&session.prompt_text);
+            let was_touched = |file: &str| {
+                session
+                    .files_written
+                    .iter()
+                    .chain(session.files_touched.iter())
+                    .any(|f| same_file(f, file))
+            };
+
+            let mut gaps: Vec<String> = files_intended
+                .iter()
+                .filter(|f| !was_touched(f))
+                .map(|f| format!("Mentioned file not changed: {}", f))
+                .collect();
+            let missed_files = gaps.len();
+            if session.interrupted {
+                gaps.push("Interrupted before finishing".to_string());
+            }
+            for denial in &session.permission_denials {
+                gaps.push(format!("Permission denied: {}", denial));
+            }
+
+            let completion_score = if !files_intended.is_empty() {
+                (files_intended.len() - missed_files) as f32 / files_intended.len() as f32
+            } else if !session.files_written.is_empty() {
+                0.8 // Work done but nothing specific to check it against
+            } else {
+                0.2
+            };
+
+            let reprompt_count = reprompts.get(&i).copied().unwrap_or(0);
+            let rework = rework_commits(session, commits);
+            let (outcome, outcome_confidence) = classify_outcome(
+                session,
+                reprompt_count > 0,
+                !rework.is_empty(),
+                !gaps.is_empty(),
+            );
+
+            IntentAnalysis {
+                prompt_text: session.prompt_text.clone(),
+                session_id: session.session_id.clone(),
+                timestamp: session.timestamp.clone(),
+                completion_score,
+                reprompt_count,
+                gaps,
+                outcome: outcome.to_string(),
+                outcome_confidence,
+                files_intended,
+                files_actual: session.files_written.clone(),
+                intent_summary: session.intent.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Outcome of a prompt and the confidence of that call.
+fn classify_outcome(
+    session: &PromptSession,
+    reprompted: bool,
+    reworked: bool,
+    has_gaps: bool,
+) -> (&'static str, f32) {
+    if session.files_written.is_empty() {
+        return match (session.tool_call_count, reprompted) {
+            (0, _) => ("abandoned", 0.8),
+            (_, true) => ("abandoned", 0.6),
+            (_, false) => ("partial", 0.5),
+        };
+    }
+    if reworked {
+        return ("reworked", 0.8);
+    }
+    if reprompted {
+        return ("reworked", 0.6);
+    }
+    if has_gaps {
+        return ("partial", 0.6);
+    }
+    ("completed", 0.85)
+}
+
+/// Roll intent analyses up into the completion and re-prompt analytics.
+pub fn summarize_intents(analytics: &mut Analytics, analyses: &[IntentAnalysis]) {
+    if analyses.is_empty() {
+        analytics.avg_intent_completion = 0.0;
+        analytics.reprompt_rate = 0.0;
+        return;
+    }
+    let total = analyses.len() as f32;
+    analytics.avg_intent_completion =
+        analyses.iter().map(|a| a.completion_score).sum::<f32>() / total;
+    // Share of prompts the developer had to restate
+    analytics.reprompt_rate = analyses
+        .iter()
+        .filter(|a| a.reprompt_count > 0)
+        .count() as f32
+        / total;
+}
+
+/// Fill `intent_analyses` and the intent analytics, unless the Mojo engine
+/// already provided them.
+pub fn apply_intent_analysis(project: &mut ProjectData) {
+    if !project.intent_analyses.is_empty() {
+        return;
+    }
+    project.intent_analyses = analyze_intents(&project.prompt_sessions, &project.commits);
+    summarize_intents(&mut project.analytics, &project.intent_analyses);
+}
+
+/// Copy newly extracted prompt goals into existing analyses.
+pub fn refresh_intent_summaries(analyses: &mut [IntentAnalysis], sessions: &[PromptSession]) {
+    let goals: HashMap<(&str, &str), &str> = sessions
+        .iter()
+        .filter_map(|s| {
+            s.intent
+                .as_deref()
+                .map(|goal| ((s.session_id.as_str(), s.timestamp.as_str()), goal))
+        })
+        .collect();
+    for analysis in analyses.iter_mut() {
+        if let Some(goal) = goals.get(&(analysis.session_id.as_str(), analysis.timestamp.as_str())) {
+            analysis.intent_summary = Some(goal.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FileChange;
+
+    fn prompt(session_id: &str, timestamp: &str, text: &str, written: &[&str]) -> PromptSession {
+        PromptSession {
+            session_id: session_id.to_string(),
+            transcript_id: session_id.to_string(),
+            prompt_text: text.to_string(),
+            timestamp: timestamp.to_string(),
+            files_written: written.iter().map(|f| f.to_string()).collect(),
+            tool_call_count: written.len() as u32,
+            ..Default::default()
+        }
+    }
+
+    fn fix_commit(timestamp: &str, path: &str) -> CommitData {
+        CommitData {
+            hash: "abc1234".to_string(),
+            timestamp: timestamp.to_string(),
+            subject: "fix: upload retry off-by-one".to_string(),
+            change_type: "bug_fix".to_string(),
+            files_changed: vec![FileChange {
+                path: path.to_string(),
+                lines_added: 3,
+                lines_removed: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_reprompts_gaps_and_rework() {
+        let sessions = vec![
+            prompt(
+                "s1",
+                "2026-03-01T10:00:00Z",
+                "Add retry logic to the upload client in src/upload/client.rs",
+                &["/home/dev/app/src/upload/client.rs"],
+            ),
+            prompt(
+                "s1",
+                "2026-03-01T10:20:00Z",
+                "The retry logic in the upload client still fails, fix it",
+                &["/home/dev/app/src/upload/client.rs"],
+            ),
+            prompt(
+                "s2",
+                "2026-03-02T09:00:00Z",
+                "Update README.md and docs/setup.md with the new flags",
+                &["/home/dev/app/README.md"],
+            ),
+        ];
+        let commits = vec![fix_commit("2026-03-03T12:00:00Z", "src/upload/client.rs")];
+
+        let analyses = analyze_intents(&sessions, &commits);
+
+        assert_eq!(analyses[0].reprompt_count, 1);
+        assert_eq!(analyses[0].outcome, "reworked");
+        assert_eq!(analyses[0].files_intended, vec!["src/upload/client.rs"]);
+        assert_eq!(analyses[0].completion_score, 1.0);
+
+        assert_eq!(analyses[2].reprompt_count, 0);
+        assert_eq!(analyses[2].outcome, "partial");
+        assert_eq!(analyses[2].completion_score, 0.5);
+        assert_eq!(
+            analyses[2].gaps,
+            vec!["Mentioned file not changed: docs/setup.md"]
+        );
+    }
+}
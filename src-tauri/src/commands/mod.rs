@@ -1,12 +1,28 @@
+mod api_key;
+mod batch;
+mod bundle;
 mod claude_api;
+mod cost;
 mod enrich;
+mod intent;
+mod profile;
+mod project_config;
 mod project;
+mod redact;
 mod scan;
 mod sessions;
 mod settings;
+mod subproject;
+mod trash;
 
+pub use api_key::*;
+pub use batch::*;
+pub use bundle::*;
 pub use enrich::*;
+pub use profile::*;
 pub use project::*;
 pub use scan::*;
 pub use sessions::*;
 pub use settings::*;
+pub use subproject::*;
+pub use trash::*;
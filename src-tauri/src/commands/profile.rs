@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Timelike};
+
+use crate::storage::db::Database;
+use crate::types::{CommitData, DeveloperProfile, FileCoupling, ProjectData, PromptSession};
+
+use super::intent::analyze_intents;
+use super::scan::detect_languages;
+
+/// Pairs co-edited fewer times than this are not reported as couplings.
+const MIN_COUPLING_COUNT: u32 = 3;
+/// Commits touching more files than this (bulk renames, formatting passes)
+/// say nothing about which files belong together.
+const MAX_COUPLING_FILES: usize = 50;
+const MAX_COUPLINGS: usize = 50;
+const PEAK_HOUR_COUNT: usize = 3;
+
+/// Tauri command: build a developer profile from a saved project, optionally
+/// limited to one commit author (matched by name or email).
+#[tauri::command]
+pub async fn get_developer_profile(
+    project_id: String,
+    author: Option<String>,
+) -> Result<DeveloperProfile, String> {
+    let project = Database::open_default()?.load_project(&project_id)?;
+    Ok(build_developer_profile(&project, author.as_deref()))
+}
+
+/// Compute a developer profile from a project's commits and prompts. With an
+/// author, only their commits count, and only prompts linked to those commits.
+pub fn build_developer_profile(project: &ProjectData, author: Option<&str>) -> DeveloperProfile {
+    let commits: Vec<CommitData> = project
+        .commits
+        .iter()
+        .filter(|c| author.is_none_or(|a| is_author(c, a)))
+        .cloned()
+        .collect();
+    let hashes: HashSet<&str> = commits.iter().map(|c| c.hash.as_str()).collect();
+    let sessions: Vec<PromptSession> = project
+        .prompt_sessions
+        .iter()
+        .filter(|s| {
+            author.is_none()
+                || s.associated_commit_hashes
+                    .iter()
+                    .any(|h| hashes.contains(h.as_str()))
+        })
+        .cloned()
+        .collect();
+
+    let mut common_change_types: HashMap<String, u32> = HashMap::new();
+    for commit in &commits {
+        *common_change_types
+            .entry(commit.change_type.clone())
+            .or_insert(0) += 1;
+    }
+
+    let total_prompts = sessions.len() as u32;
+    let tool_call_frequency = if sessions.is_empty() {
+        0.0
+    } else {
+        sessions.iter().map(|s| s.tool_call_count).sum::<u32>() as f32 / total_prompts as f32
+    };
+    let avg_commit_granularity = if commits.is_empty() {
+        0.0
+    } else {
+        commits.iter().map(|c| c.files_changed.len()).sum::<usize>() as f32 / commits.len() as f32
+    };
+
+    let session_lengths = session_lengths_mins(&sessions);
+
+    DeveloperProfile {
+        preferred_languages: detect_languages(&commits),
+        avg_session_length_mins: if session_lengths.is_empty() {
+            0.0
+        } else {
+            session_lengths.values().sum::<f32>() / session_lengths.len() as f32
+        },
+        reprompt_rate: reprompt_rate(project, &sessions),
+        tool_call_frequency,
+        common_change_types,
+        peak_hours: peak_hours(&commits),
+        avg_commit_granularity,
+        file_couplings: file_couplings(&commits),
+        total_sessions: sessions
+            .iter()
+            .map(|s| s.session_id.as_str())
+            .collect::<HashSet<_>>()
+            .len() as u32,
+        total_prompts,
+    }
+}
+
+fn is_author(commit: &CommitData, author: &str) -> bool {
+    commit.author_name.eq_ignore_ascii_case(author)
+        || commit.author_email.eq_ignore_ascii_case(author)
+}
+
+/// Minutes from each session's first prompt to the end of its last one.
+fn session_lengths_mins(sessions: &[PromptSession]) -> HashMap<&str, f32> {
+    let mut spans: HashMap<&str, (i64, i64)> = HashMap::new();
+    for session in sessions {
+        let Ok(start) = DateTime::parse_from_rfc3339(&session.timestamp) else {
+            continue;
+        };
+        let end = session
+            .time_end
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .unwrap_or(start);
+        let span = spans
+            .entry(session.session_id.as_str())
+            .or_insert((start.timestamp(), end.timestamp()));
+        span.0 = span.0.min(start.timestamp());
+        span.1 = span.1.max(end.timestamp());
+    }
+    spans
+        .into_iter()
+        .map(|(id, (start, end))| (id, (end - start) as f32 / 60.0))
+        .collect()
+}
+
+/// Share of prompts that were restated, from the saved intent analyses when
+/// they cover these prompts.
+fn reprompt_rate(project: &ProjectData, sessions: &[PromptSession]) -> f32 {
+    if sessions.is_empty() {
+        return 0.0;
+    }
+    let keys: HashSet<(&str, &str)> = sessions
+        .iter()
+        .map(|s| (s.session_id.as_str(), s.timestamp.as_str()))
+        .collect();
+    let saved: Vec<u32> = project
+        .intent_analyses
+        .iter()
+        .filter(|a| keys.contains(&(a.session_id.as_str(), a.timestamp.as_str())))
+        .map(|a| a.reprompt_count)
+        .collect();
+    let counts = if saved.is_empty() {
+        analyze_intents(sessions, &project.commits)
+            .iter()
+            .map(|a| a.reprompt_count)
+            .collect()
+    } else {
+        saved
+    };
+    counts.iter().filter(|&&n| n > 0).count() as f32 / counts.len() as f32
+}
+
+/// The busiest hours of the day, in each commit's own time zone.
+fn peak_hours(commits: &[CommitData]) -> Vec<u32> {
+    let mut counts = [0u32; 24];
+    for commit in commits {
+        if let Ok(time) = DateTime::parse_from_rfc3339(&commit.timestamp) {
+            counts[time.hour() as usize] += 1;
+        }
+    }
+    let mut hours: Vec<u32> = (0..24).filter(|&h| counts[h as usize] > 0).collect();
+    hours.sort_by_key(|&h| std::cmp::Reverse(counts[h as usize]));
+    hours.truncate(PEAK_HOUR_COUNT);
+    hours.sort_unstable();
+    hours
+}
+
+/// Pairs of files changed in the same commit. Support is the share of all
+/// commits containing the pair; confidence is the share of commits touching
+/// the less frequently changed file of the two that also touch the other.
+fn file_couplings(commits: &[CommitData]) -> Vec<FileCoupling> {
+    let mut file_counts: HashMap<&str, u32> = HashMap::new();
+    let mut pair_counts: BTreeMap<(&str, &str), u32> = BTreeMap::new();
+    for commit in commits {
+        let mut files: Vec<&str> = commit
+            .files_changed
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        files.sort_unstable();
+        files.dedup();
+        for file in &files {
+            *file_counts.entry(file).or_insert(0) += 1;
+        }
+        if files.len() > MAX_COUPLING_FILES {
+            continue;
+        }
+        for (i, a) in files.iter().enumerate() {
+            for b in &files[i + 1..] {
+                *pair_counts.entry((a, b)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut couplings: Vec<FileCoupling> = pair_counts
+        .into_iter()
+        .filter(|&(_, count)| count >= MIN_COUPLING_COUNT)
+        .map(|((a, b), count)| {
+            let rarer = file_counts[a].min(file_counts[b]);
+            FileCoupling {
+                file_a: a.to_string(),
+                file_b: b.to_string(),
+                count,
+                support: count as f32 / commits.len() as f32,
+                confidence: count as f32 / rarer as f32,
+            }
+        })
+        .collect();
+    couplings.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(b.confidence.total_cmp(&a.confidence))
+    });
+    couplings.truncate(MAX_COUPLINGS);
+    couplings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FileChange;
+
+    fn commit(hash: &str, author: &str, timestamp: &str, files: &[&str]) -> CommitData {
+        CommitData {
+            hash: hash.to_string(),
+            author_name: author.to_string(),
+            author_email: format!("{}@example.com", author.to_lowercase()),
+            timestamp: timestamp.to_string(),
+            change_type: "feature".to_string(),
+            files_changed: files
+                .iter()
+                .map(|p| FileChange {
+                    path: p.to_string(),
+                    lines_added: 1,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn prompt(session_id: &str, timestamp: &str, time_end: &str, commit: &str) -> PromptSession {
+        PromptSession {
+            session_id: session_id.to_string(),
+            transcript_id: session_id.to_string(),
+            prompt_text: format!("prompt at {}", timestamp),
+            timestamp: timestamp.to_string(),
+            associated_commit_hashes: vec![commit.to_string()],
+            tool_call_count: 4,
+            time_end: Some(time_end.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn profiles_one_author_with_couplings_and_session_lengths() {
+        let commits = vec![
+            commit(
+                "a1",
+                "Ada",
+                "2026-03-02T09:10:00+01:00",
+                &["src/db.rs", "src/schema.rs"],
+            ),
+            commit(
+                "a2",
+                "Ada",
+                "2026-03-02T09:40:00+01:00",
+                &["src/db.rs", "src/schema.rs"],
+            ),
+            commit(
+                "a3",
+                "Ada",
+                "2026-03-03T14:00:00+01:00",
+                &["src/db.rs", "src/schema.rs", "src/main.rs"],
+            ),
+            commit("a4", "Ada", "2026-03-04T09:05:00+01:00", &["src/db.rs"]),
+            commit(
+                "b1",
+                "Bo",
+                "2026-03-04T22:00:00+01:00",
+                &["src/db.rs", "src/schema.rs"],
+            ),
+        ];
+        let sessions = vec![
+            prompt("s1", "2026-03-02T08:00:00Z", "2026-03-02T08:10:00Z", "a1"),
+            prompt("s1", "2026-03-02T08:30:00Z", "2026-03-02T08:40:00Z", "a2"),
+            prompt("s2", "2026-03-04T21:00:00Z", "2026-03-04T21:20:00Z", "b1"),
+        ];
+        let project = ProjectData {
+            commits,
+            prompt_sessions: sessions,
+            ..Default::default()
+        };
+
+        let profile = build_developer_profile(&project, Some("ada"));
+        assert_eq!(profile.preferred_languages, vec!["Rust"]);
+        assert_eq!(profile.total_sessions, 1);
+        assert_eq!(profile.total_prompts, 2);
+        assert_eq!(profile.avg_session_length_mins, 40.0);
+        assert_eq!(profile.tool_call_frequency, 4.0);
+        assert_eq!(profile.peak_hours, vec![9, 14]);
+        assert_eq!(profile.avg_commit_granularity, 2.0);
+        assert_eq!(profile.common_change_types["feature"], 4);
+
+        // db.rs and schema.rs change together in 3 of Ada's 4 commits
+        assert_eq!(profile.file_couplings.len(), 1);
+        let coupling = &profile.file_couplings[0];
+        assert_eq!(
+            (coupling.file_a.as_str(), coupling.file_b.as_str()),
+            ("src/db.rs", "src/schema.rs")
+        );
+        assert_eq!(coupling.count, 3);
+        assert_eq!(coupling.support, 0.75);
+        assert_eq!(coupling.confidence, 1.0);
+
+        // Everyone's commits: the pair appears once more
+        let everyone = build_developer_profile(&project, None);
+        assert_eq!(everyone.total_sessions, 2);
+        assert_eq!(everyone.file_couplings[0].count, 4);
+    }
+}
@@ -1,17 +1,17 @@
+use crate::storage::db::Database;
 use crate::types::{
     FeatureCluster, FunctionHistory, ProjectData, ProjectSummary, SearchResults,
 };
 
+/// Tauri command: load a project saved by `scan_repository`.
 #[tauri::command]
 pub async fn get_project_data(project_id: String) -> Result<ProjectData, String> {
-    // TODO: Load enriched data from storage
-    Err(format!("Project not found: {}", project_id))
+    Database::open_default()?.load_project(&project_id)
 }
 
 #[tauri::command]
 pub async fn list_projects() -> Result<Vec<ProjectSummary>, String> {
-    // TODO: Query SQLite for saved projects
-    Ok(vec![])
+    Database::open_default()?.list_projects()
 }
 
 #[tauri::command]
@@ -0,0 +1,388 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+use sha2::{Digest, Sha256};
+
+use crate::types::{
+    AppSettings, AttributionConfig, CommitData, DateRange, ProjectConfig, ProjectConfigSource,
+    ProjectData,
+};
+
+use super::redact::redaction_config_problems;
+use super::scan::{compute_analytics, detect_languages};
+use super::subproject::rescope_feature;
+
+/// Per-repo config file, read from the repo root.
+pub const PROJECT_CONFIG_FILE: &str = ".codelens.toml";
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Read and validate the repo's `.codelens.toml`, if it has one.
+pub fn load_project_config(repo_path: &Path) -> Result<Option<ProjectConfigSource>, String> {
+    let path = repo_path.join(PROJECT_CONFIG_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config =
+        parse_project_config(&text).map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+    log::info!("Using project config {}", path.display());
+
+    Ok(Some(ProjectConfigSource {
+        path: path.to_string_lossy().to_string(),
+        sha256: hex::encode(Sha256::digest(text.as_bytes())),
+        config,
+    }))
+}
+
+/// Parse a config file, reporting every invalid value at once.
+pub fn parse_project_config(text: &str) -> Result<ProjectConfig, String> {
+    let config: ProjectConfig = toml::from_str(text).map_err(|e| e.to_string())?;
+
+    let mut problems = Vec::new();
+    if let Some(version) = config.version.filter(|&v| v != 1) {
+        problems.push(format!("version {} is not supported; expected 1", version));
+    }
+    if config.scan.max_commits == Some(0) {
+        problems.push("scan.max_commits must be at least 1".to_string());
+    }
+    for (i, pattern) in config.scan.ignore.iter().enumerate() {
+        if let Err(e) = Pattern::new(pattern) {
+            problems.push(format!("scan.ignore[{}] {:?} is not a valid glob: {}", i, pattern, e));
+        }
+    }
+    if let Some(eps) = config.clustering.eps.filter(|&eps| !(eps > 0.0 && eps <= 2.0)) {
+        problems.push(format!("clustering.eps must be above 0 and at most 2, got {}", eps));
+    }
+    if config.clustering.min_samples == Some(0) {
+        problems.push("clustering.min_samples must be at least 1".to_string());
+    }
+    if let Some(size) = config.clustering.embedding_batch_size {
+        if !(1..=1024).contains(&size) {
+            problems.push("clustering.embedding_batch_size must be between 1 and 1024".into());
+        }
+    }
+    if let Some(attribution) = &config.attribution {
+        if attribution
+            .markers
+            .iter()
+            .chain(&attribution.authors)
+            .any(|rule| rule.trim().is_empty())
+        {
+            problems.push("attribution markers and authors must not be empty".to_string());
+        }
+    }
+    problems.extend(redaction_config_problems(&config.redaction));
+    let mut names = HashSet::new();
+    for (i, subproject) in config.subprojects.iter().enumerate() {
+        if subproject.name.trim().is_empty() {
+            problems.push(format!("subprojects[{}] needs a name", i));
+        } else if !names.insert(subproject.name.as_str()) {
+            problems.push(format!("subproject {:?} is defined twice", subproject.name));
+        }
+        if subproject.paths.is_empty() {
+            problems.push(format!("subprojects[{}] needs at least one path", i));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(config)
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+/// Let the config override the app settings for this repo's scans.
+pub fn apply_to_settings(config: &ProjectConfig, settings: &mut AppSettings) {
+    if let Some(max_commits) = config.scan.max_commits {
+        settings.max_commits = max_commits;
+    }
+    if let Some(eps) = config.clustering.eps {
+        settings.clustering_eps = eps;
+    }
+    if let Some(min_samples) = config.clustering.min_samples {
+        settings.clustering_min_samples = min_samples;
+    }
+    if let Some(size) = config.clustering.embedding_batch_size {
+        settings.embedding_batch_size = size;
+    }
+}
+
+/// The built-in Claude Code attribution: co-author and generator markers, or
+/// a Claude author.
+fn default_attribution(author_name: &str, body: &str) -> bool {
+    body.contains("Co-Authored-By: Claude")
+        || body.contains("co-authored-by: Claude")
+        || body.contains("Generated by Claude")
+        || author_name.contains("Claude")
+}
+
+/// Ignore and attribution rules of a scan, compiled from the project config.
+#[derive(Default)]
+pub struct ScanRules {
+    ignore: Vec<Pattern>,
+    /// Lowercased custom rules; `None` keeps the built-in attribution
+    attribution: Option<AttributionConfig>,
+}
+
+impl ScanRules {
+    /// Rules of a validated config; without one, nothing is ignored and the
+    /// built-in attribution applies.
+    pub fn new(config: Option<&ProjectConfig>) -> Self {
+        let Some(config) = config else {
+            return Self::default();
+        };
+        let lowercase = |rules: &[String]| rules.iter().map(|r| r.to_lowercase()).collect();
+        Self {
+            ignore: config
+                .scan
+                .ignore
+                .iter()
+                .filter_map(|p| Pattern::new(p).ok())
+                .collect(),
+            attribution: config.attribution.as_ref().map(|a| AttributionConfig {
+                default_rules: a.default_rules,
+                markers: lowercase(&a.markers),
+                authors: lowercase(&a.authors),
+            }),
+        }
+    }
+
+    /// Patterns without a `/` match file names at any depth, like in
+    /// `.gitignore`; the others match the whole repo-relative path.
+    fn is_ignored(&self, path: &str) -> bool {
+        let path = path.trim_start_matches("./");
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        self.ignore.iter().any(|pattern| {
+            if pattern.as_str().contains('/') {
+                pattern.matches_with(path, GLOB_OPTIONS)
+            } else {
+                pattern.matches_with(file_name, GLOB_OPTIONS)
+            }
+        })
+    }
+
+    /// Whether a commit counts as a Claude Code commit.
+    pub fn is_claude_code(&self, commit: &CommitData) -> bool {
+        let Some(rules) = &self.attribution else {
+            return default_attribution(&commit.author_name, &commit.body);
+        };
+        if rules.default_rules && default_attribution(&commit.author_name, &commit.body) {
+            return true;
+        }
+        let message = format!("{}\n{}", commit.subject, commit.body).to_lowercase();
+        let name = commit.author_name.to_lowercase();
+        let email = commit.author_email.to_lowercase();
+        rules.markers.iter().any(|m| message.contains(m.as_str()))
+            || rules
+                .authors
+                .iter()
+                .any(|a| name.contains(a.as_str()) || email.contains(a.as_str()))
+    }
+
+    /// Remove ignored files from the commits, dropping commits that only
+    /// touched ignored files. Returns whether any commit changed.
+    pub fn drop_ignored_files(&self, commits: &mut Vec<CommitData>) -> bool {
+        if self.ignore.is_empty() {
+            return false;
+        }
+        let mut changed = false;
+        commits.retain_mut(|commit| {
+            if commit.files_changed.is_empty() {
+                return true;
+            }
+            let before = commit.files_changed.len();
+            commit.files_changed.retain(|f| !self.is_ignored(&f.path));
+            changed |= commit.files_changed.len() != before;
+            !commit.files_changed.is_empty()
+        });
+        changed
+    }
+
+    /// Re-attribute commits when the config has attribution rules. Returns
+    /// whether any commit changed.
+    fn reattribute(&self, commits: &mut [CommitData]) -> bool {
+        if self.attribution.is_none() {
+            return false;
+        }
+        let mut changed = false;
+        for commit in commits.iter_mut() {
+            let is_claude_code = self.is_claude_code(commit);
+            changed |= is_claude_code != commit.is_claude_code;
+            commit.is_claude_code = is_claude_code;
+        }
+        changed
+    }
+
+    /// Apply the rules to the Mojo engine's output, recomputing the features,
+    /// analytics and repository totals built from the commits. The engine's ML
+    /// metrics are kept.
+    pub fn apply_to_engine_output(&self, project: &mut ProjectData) {
+        let dropped = self.drop_ignored_files(&mut project.commits);
+        let reattributed = self.reattribute(&mut project.commits);
+        if !dropped && !reattributed {
+            return;
+        }
+
+        let commit_lookup: HashMap<&str, &CommitData> =
+            project.commits.iter().map(|c| (c.hash.as_str(), c)).collect();
+        project.features = project
+            .features
+            .iter()
+            .filter_map(|feature| rescope_feature(feature, &commit_lookup))
+            .collect();
+
+        let engine = std::mem::replace(
+            &mut project.analytics,
+            compute_analytics(&project.commits, &project.features),
+        );
+        let analytics = &mut project.analytics;
+        analytics.avg_prompt_similarity = engine.avg_prompt_similarity;
+        analytics.avg_intent_completion = engine.avg_intent_completion;
+        analytics.reprompt_rate = engine.reprompt_rate;
+        analytics.pattern_count = engine.pattern_count;
+        analytics.embedding_coverage = engine.embedding_coverage;
+
+        let commits = &project.commits;
+        project.repository.total_commits = commits.len() as u32;
+        project.repository.date_range = DateRange {
+            start: commits.last().map(|c| c.timestamp.clone()).unwrap_or_default(),
+            end: commits.first().map(|c| c.timestamp.clone()).unwrap_or_default(),
+        };
+        project.repository.languages_detected = detect_languages(commits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FileChange;
+
+    fn commit(hash: &str, author: &str, body: &str, files: &[&str]) -> CommitData {
+        CommitData {
+            hash: hash.to_string(),
+            author_name: author.to_string(),
+            author_email: format!("{}@example.com", author.to_lowercase()),
+            body: body.to_string(),
+            files_changed: files
+                .iter()
+                .map(|p| FileChange {
+                    path: p.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn config_overrides_settings_and_filters_commits() {
+        let config = parse_project_config(
+            r#"
+            version = 1
+
+            [scan]
+            max_commits = 500
+            ignore = ["*.lock", "vendor/**"]
+
+            [clustering]
+            eps = 0.4
+
+            [attribution]
+            markers = ["AI-Assisted: true"]
+            authors = ["release-bot"]
+
+            [[subprojects]]
+            name = "billing"
+            paths = ["services/billing"]
+            "#,
+        )
+        .unwrap();
+        let mut settings = AppSettings::default();
+        apply_to_settings(&config, &mut settings);
+        assert_eq!(settings.max_commits, 500);
+        assert_eq!(settings.clustering_eps, 0.4);
+        assert_eq!(settings.clustering_min_samples, 2);
+        assert_eq!(config.subprojects[0].paths, ["services/billing"]);
+
+        let rules = ScanRules::new(Some(&config));
+        let mut commits = vec![
+            commit("a", "Ana", "", &["Cargo.lock", "web/package.lock", "src/main.rs"]),
+            commit("b", "Ana", "ai-assisted: TRUE", &["vendor/lib/x.c"]),
+            commit("c", "Release-Bot", "", &[]),
+            commit("d", "Ana", "Co-Authored-By: Claude", &["src/lib.rs"]),
+        ];
+        assert!(rules.drop_ignored_files(&mut commits));
+        let hashes: Vec<&str> = commits.iter().map(|c| c.hash.as_str()).collect();
+        assert_eq!(hashes, ["a", "c", "d"]);
+        assert_eq!(commits[0].files_changed.len(), 1);
+
+        let claude: Vec<bool> = commits.iter().map(|c| rules.is_claude_code(c)).collect();
+        assert_eq!(claude, [false, true, true]);
+        assert!(rules.is_claude_code(&commit("e", "Ana", "AI-Assisted: true", &[])));
+
+        let error = parse_project_config(
+            r#"
+            version = 2
+            [clustering]
+            eps = 0
+            [scan]
+            ignore = ["src/[a"]
+            [[subprojects]]
+            name = "web"
+            paths = []
+            [[subprojects]]
+            name = "web"
+            paths = ["web"]
+            "#,
+        )
+        .unwrap_err();
+        for problem in ["version 2", "clustering.eps", "scan.ignore[0]", "defined twice", "path"] {
+            assert!(error.contains(problem), "{} missing from {}", problem, error);
+        }
+        let typo = parse_project_config("[clustering]\nepss = 0.4\n").unwrap_err();
+        assert!(typo.contains("unknown field `epss`"), "{}", typo);
+    }
+
+    #[test]
+    fn redaction_table_configures_the_redactor() {
+        use super::super::redact::Redactor;
+
+        let config = parse_project_config(
+            r#"
+            [redaction]
+            disabled_detectors = ["email"]
+            custom_patterns = [{ name = "customer_id", pattern = '\bCUST-\d{6}\b' }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.redaction.min_entropy, 4.0);
+        let redactor = Redactor::for_project(Some(&config)).unwrap();
+        let mut counts = HashMap::new();
+        let text = redactor.redact("CUST-123456 asked ana@example.com", &mut counts);
+        assert_eq!(text, "[REDACTED:customer_id] asked ana@example.com");
+
+        let off = parse_project_config("[redaction]\nenabled = false\n").unwrap();
+        assert!(!Redactor::for_project(Some(&off)).unwrap().is_enabled());
+        assert!(Redactor::for_project(None).unwrap().is_enabled());
+
+        let error = parse_project_config(
+            r#"
+            [redaction]
+            disabled_detectors = ["emails"]
+            min_entropy = 0.0
+            custom_patterns = [{ name = "", pattern = "(" }]
+            "#,
+        )
+        .unwrap_err();
+        for problem in ["\"emails\"", "min_entropy", "needs a name", "not a valid regex"] {
+            assert!(error.contains(problem), "{} missing from {}", problem, error);
+        }
+    }
+}
@@ -2,29 +2,60 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::process::Command;
 
+use crate::claude::pricing::PricingTable;
 use crate::mojo_bridge::{parser::parse_preprocessed_output, runner};
+use crate::storage::db::Database;
 use crate::types::{
-    Analytics, CommitData, DateRange, FeatureCluster, FileChange, ProjectData, RepositoryInfo,
-    WeekVelocity,
+    Analytics, AppSettings, CommitData, DateRange, FeatureCluster, FileChange, ProjectData,
+    RepositoryInfo, Subproject, WeekVelocity,
 };
 
+use super::cost::apply_costs;
+use super::enrich::restore_cached_enrichments;
+use super::intent::apply_intent_analysis;
+use super::project_config::{apply_to_settings, load_project_config, ScanRules};
+use super::redact::Redactor;
 use super::sessions::parse_sessions_for_repo;
+use super::settings::scan_settings;
+use super::subproject::build_subproject_summaries;
 
 #[tauri::command]
-pub async fn scan_repository(path: String) -> Result<ProjectData, String> {
+pub async fn scan_repository(
+    path: String,
+    subprojects: Option<Vec<Subproject>>,
+) -> Result<ProjectData, String> {
     let repo_path = Path::new(&path);
+    let mut settings = scan_settings();
+    let pricing = PricingTable::load();
 
     // Validate .git directory exists
     if !repo_path.join(".git").exists() {
         return Err(format!("Not a git repository: {}", path));
     }
 
+    // The repo's .codelens.toml overrides the app settings for this scan
+    let project_config = load_project_config(repo_path)?;
+    let config = project_config.as_ref().map(|source| &source.config);
+    if let Some(config) = config {
+        apply_to_settings(config, &mut settings);
+    }
+    let rules = ScanRules::new(config);
+    let redactor = Redactor::for_project(config)?;
+    let subprojects = match subprojects.filter(|s| !s.is_empty()) {
+        Some(subprojects) => subprojects,
+        None => config.map(|c| c.subprojects.clone()).unwrap_or_default(),
+    };
+
     // Try Mojo engine first, fall back to Rust pipeline
     if runner::is_engine_available(None) {
-        match try_mojo_engine(&path) {
+        match try_mojo_engine(&path, &settings) {
             Ok(mut project_data) => {
+                rules.apply_to_engine_output(&mut project_data);
+                project_data.project_config = project_config;
+
                 // Mojo engine succeeded — augment with session data from Rust
                 let mut prompt_sessions = parse_sessions_for_repo(&path);
+                project_data.redaction = redactor.redact_sessions(&mut prompt_sessions);
                 correlate_prompts_to_commits(&mut prompt_sessions, &project_data.commits);
                 link_prompts_to_features(
                     &mut prompt_sessions,
@@ -34,18 +65,11 @@ pub async fn scan_repository(path: String) -> Result<ProjectData, String> {
                 project_data.analytics.total_prompts_detected =
                     prompt_sessions.len() as u32;
                 project_data.prompt_sessions = prompt_sessions;
+                apply_costs(&mut project_data, &pricing);
+                project_data.subprojects =
+                    build_subproject_summaries(&project_data, &subprojects);
 
-                // Optionally enrich with Claude API
-                if std::env::var("ANTHROPIC_API_KEY").is_ok() {
-                    match super::enrich::enrich_project_features(&mut project_data).await {
-                        Ok(count) if count > 0 => {
-                            log::info!("Enriched {} features with Claude API", count);
-                        }
-                        Ok(_) => {}
-                        Err(e) => log::warn!("Feature enrichment failed: {}", e),
-                    }
-                }
-
+                finish_scan(&mut project_data, &settings);
                 return Ok(project_data);
             }
             Err(e) => {
@@ -67,6 +91,7 @@ pub async fn scan_repository(path: String) -> Result<ProjectData, String> {
             "log",
             "--format=%H%x01%an%x01%ae%x01%aI%x01%s%x01%b%x00",
             "--no-merges",
+            &format!("--max-count={}", settings.max_commits),
         ])
         .current_dir(&path)
         .output()
@@ -104,12 +129,6 @@ pub async fn scan_repository(path: String) -> Result<ProjectData, String> {
             String::new()
         };
 
-        // Detect Claude Code by co-author markers
-        let is_claude_code = body.contains("Co-Authored-By: Claude")
-            || body.contains("co-authored-by: Claude")
-            || body.contains("Generated by Claude")
-            || author_name.contains("Claude");
-
         // Classify change type from commit message keywords
         let lower_subject = subject.to_lowercase();
         let change_type = classify_change_type(&lower_subject);
@@ -117,21 +136,25 @@ pub async fn scan_repository(path: String) -> Result<ProjectData, String> {
         // Get file stats via git diff-tree
         let files_changed = get_file_stats(&path, &hash);
 
-        commits.push(CommitData {
+        let mut commit = CommitData {
             hash,
             author_name,
             author_email,
             timestamp,
             subject,
             body,
-            is_claude_code,
+            is_claude_code: false,
             session_id: None,
             change_type,
             change_type_confidence: 0.7,
             cluster_id: -1, // assigned during feature grouping
             files_changed,
-        });
+        };
+        // Detect Claude Code by co-author markers or the repo's attribution rules
+        commit.is_claude_code = rules.is_claude_code(&commit);
+        commits.push(commit);
     }
+    rules.drop_ignored_files(&mut commits);
 
     // Sort commits by timestamp (newest first)
     commits.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
@@ -165,6 +188,7 @@ pub async fn scan_repository(path: String) -> Result<ProjectData, String> {
 
     // Parse Claude Code session logs and correlate prompts to commits
     let mut prompt_sessions = parse_sessions_for_repo(&path);
+    let redaction = redactor.redact_sessions(&mut prompt_sessions);
     correlate_prompts_to_commits(&mut prompt_sessions, &commits);
 
     // Link prompts ↔ features bidirectionally and build sub-features
@@ -175,29 +199,36 @@ pub async fn scan_repository(path: String) -> Result<ProjectData, String> {
     analytics.total_prompts_detected = prompt_sessions.len() as u32;
 
     let mut project_data = ProjectData {
+        id: None,
         repository,
         commits,
         features,
         prompt_sessions,
         analytics,
+        subprojects: vec![],
+        redaction,
+        intent_analyses: vec![],
+        project_config,
+        engine: None,
     };
-
-    // Optionally enrich features with Claude API if key is configured
-    if std::env::var("ANTHROPIC_API_KEY").is_ok() {
-        match super::enrich::enrich_project_features(&mut project_data).await {
-            Ok(count) if count > 0 => {
-                log::info!("Enriched {} features with Claude API", count);
-            }
-            Ok(_) => {}
-            Err(e) => {
-                log::warn!("Feature enrichment failed: {}", e);
-            }
-        }
-    }
+    apply_costs(&mut project_data, &pricing);
+    project_data.subprojects = build_subproject_summaries(&project_data, &subprojects);
+    finish_scan(&mut project_data, &settings);
 
     Ok(project_data)
 }
 
+/// Reuse cached Claude enrichments, analyze prompt intents and store the scan,
+/// so `enrich_features` and `get_project_data` can find it by id.
+fn finish_scan(project_data: &mut ProjectData, settings: &AppSettings) {
+    restore_cached_enrichments(project_data, settings);
+    apply_intent_analysis(project_data);
+    match Database::open_default().and_then(|db| db.save_project(project_data)) {
+        Ok(id) => log::info!("Saved scan of {} as project {}", project_data.repository.path, id),
+        Err(e) => log::warn!("Failed to save project: {}", e),
+    }
+}
+
 fn classify_change_type(subject: &str) -> String {
     if subject.starts_with("feat") || subject.contains("add ") || subject.contains("implement") {
         "new_feature".to_string()
@@ -362,6 +393,7 @@ fn build_feature(
         narrative: None,
         intent: None,
         key_decisions: vec![],
+        significant_changes: vec![],
         commit_hashes,
         time_start,
         time_end,
@@ -372,6 +404,7 @@ fn build_feature(
         change_type_distribution: change_type_dist,
         dependencies: vec![],
         sub_features: vec![], // populated during prompt-feature linking
+        cost_usd: 0.0,
     }
 }
 
@@ -382,7 +415,7 @@ fn parse_timestamp(ts: &str) -> i64 {
         .unwrap_or(0)
 }
 
-fn compute_analytics(commits: &[CommitData], features: &[FeatureCluster]) -> Analytics {
+pub fn compute_analytics(commits: &[CommitData], features: &[FeatureCluster]) -> Analytics {
     let mut file_counts: HashMap<String, u32> = HashMap::new();
     let mut function_counts: HashMap<String, u32> = HashMap::new();
     let mut change_type_totals: HashMap<String, u32> = HashMap::new();
@@ -451,6 +484,7 @@ fn compute_analytics(commits: &[CommitData], features: &[FeatureCluster]) -> Ana
             week,
             features,
             commits,
+            cost_usd: 0.0,
         })
         .collect();
     velocity_by_week.sort_by(|a, b| a.week.cmp(&b.week));
@@ -470,6 +504,9 @@ fn compute_analytics(commits: &[CommitData], features: &[FeatureCluster]) -> Ana
         reprompt_rate: 0.0,
         pattern_count: 0,
         embedding_coverage: 0.0,
+        total_cost_usd: 0.0,
+        avg_cost_per_feature: 0.0,
+        pricing_version: String::new(),
     }
 }
 
@@ -558,6 +595,8 @@ fn link_prompts_to_features(
                         lines_removed,
                         change_type: sub_change_type,
                         model: session.model.clone(),
+                        cost_usd: 0.0,
+                        intent: session.intent.clone(),
                     });
             }
         }
@@ -656,7 +695,7 @@ fn correlate_prompts_to_commits(
     }
 }
 
-fn detect_languages(commits: &[CommitData]) -> Vec<String> {
+pub fn detect_languages(commits: &[CommitData]) -> Vec<String> {
     let mut ext_counts: HashMap<String, u32> = HashMap::new();
 
     for commit in commits {
@@ -709,29 +748,20 @@ fn detect_languages(commits: &[CommitData]) -> Vec<String> {
 
 /// Try running the Mojo engine and parsing its output.
 /// Returns ProjectData on success, or an error string.
-fn try_mojo_engine(repo_path: &str) -> Result<ProjectData, String> {
+fn try_mojo_engine(repo_path: &str, settings: &AppSettings) -> Result<ProjectData, String> {
     let output_path = std::env::temp_dir()
         .join(format!("codelens-{}.json", uuid::Uuid::new_v4()))
         .to_string_lossy()
         .to_string();
 
-    let models_dir = std::env::var("CODELENS_MODELS_DIR")
-        .unwrap_or_else(|_| "./mojo-engine/src/models".to_string());
-
     // Enable verbose logging in debug builds or when CODELENS_VERBOSE is set
     let verbose = cfg!(debug_assertions) || std::env::var("CODELENS_VERBOSE").is_ok();
 
-    let max_commits: u32 = std::env::var("CODELENS_MAX_COMMITS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(2000);
-
-    runner::run_mojo_engine(
+    let engine = runner::run_mojo_engine(
         repo_path,
         &output_path,
-        &models_dir,
+        settings,
         verbose,
-        max_commits,
         |progress| {
             log::info!(
                 "[mojo] {}: {} ({})",
@@ -743,7 +773,15 @@ fn try_mojo_engine(repo_path: &str) -> Result<ProjectData, String> {
         None,
     )?;
 
-    let result = parse_preprocessed_output(Path::new(&output_path));
+    let result = parse_preprocessed_output(Path::new(&output_path)).and_then(|project| {
+        match &project.engine {
+            Some(output) if output.protocol_version != engine.protocol_version => Err(format!(
+                "Mojo engine announced protocol {} but wrote protocol {} output",
+                engine.protocol_version, output.protocol_version
+            )),
+            _ => Ok(project),
+        }
+    });
 
     // Clean up temp file
     let _ = std::fs::remove_file(&output_path);
@@ -1,197 +1,536 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde_json::Value;
 
-use crate::types::{PromptSession, TokenUsage};
+use crate::storage::db::Database;
+use crate::types::{PromptSession, SessionsResult, TokenUsage, ToolError};
 
-/// Find the Claude Code project directory for a given repo path.
-/// Claude Code encodes paths by replacing `/` with `-`, e.g.:
-///   /Users/me/project → -Users-me-project
-fn find_claude_project_dir(repo_path: &str) -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
-    let claude_projects = home.join(".claude").join("projects");
+use super::redact::project_redactor;
 
-    if !claude_projects.exists() {
-        return None;
+/// Directory where Claude Code keeps one subdirectory of session logs per
+/// working directory.
+fn claude_projects_dir() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".claude").join("projects"))
+}
+
+/// Claude Code project directories that may hold a repo's sessions: the repo's
+/// own directory plus those of sessions started from a subdirectory (e.g.
+/// `services/billing`), which Claude Code stores under
+/// `<encoded-repo>-services-billing`. Claude Code encodes paths by replacing
+/// `/` with `-`, e.g. `/Users/me/project` → `-Users-me-project`, so sibling
+/// repos such as `project-v2` match too; their transcripts are filtered out by
+/// `cwd` later.
+fn candidate_project_dirs(claude_projects: &Path, repo_path: &str) -> Vec<PathBuf> {
+    let encoded = repo_path.trim_end_matches('/').replace('/', "-");
+    let prefix = format!("{}-", encoded);
+
+    let mut dirs_found: Vec<PathBuf> = match fs::read_dir(claude_projects) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name == encoded || name.starts_with(&prefix)
+            })
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect(),
+        Err(_) => vec![],
+    };
+    dirs_found.sort();
+    dirs_found
+}
+
+/// Parse all Claude Code session JSONL files for a given repo path.
+/// Returns a list of PromptSession — one per user prompt found.
+pub fn parse_sessions_for_repo(repo_path: &str) -> Vec<PromptSession> {
+    let files = transcript_files_for_repo(repo_path);
+    if files.is_empty() {
+        log::info!("No Claude Code transcripts found for: {}", repo_path);
+    }
+
+    let mut all_sessions: Vec<PromptSession> =
+        files.into_iter().flat_map(|file| file.sessions).collect();
+
+    // Sessions archived by delete_sessions live on in their storage snapshots
+    match Database::open_default().and_then(|db| db.load_session_snapshots(repo_path)) {
+        Ok(archived) => merge_archived(&mut all_sessions, archived),
+        Err(e) => log::warn!("Could not load archived sessions: {}", e),
+    }
+
+    // Sort by timestamp (newest first)
+    all_sessions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    all_sessions
+}
+
+/// Add archived prompts that aren't already in `sessions`. A prompt is identified
+/// by its logical session and timestamp, since the transcript that recorded it
+/// may be a resumed copy of the one still on disk.
+fn merge_archived(sessions: &mut Vec<PromptSession>, archived: Vec<PromptSession>) {
+    let mut seen: HashSet<(String, String)> = sessions
+        .iter()
+        .map(|s| (s.session_id.clone(), s.timestamp.clone()))
+        .collect();
+    sessions.extend(
+        archived
+            .into_iter()
+            .filter(|s| seen.insert((s.session_id.clone(), s.timestamp.clone()))),
+    );
+}
+
+/// A JSONL transcript on disk together with the prompts parsed from it.
+pub(super) struct TranscriptFile {
+    pub path: PathBuf,
+    pub transcript_id: String,
+    /// Logical session id (shared with the session a resumed transcript continues)
+    pub session_id: String,
+    pub first_timestamp: String,
+    pub last_timestamp: String,
+    pub sessions: Vec<PromptSession>,
+}
+
+/// Every transcript file Claude Code recorded for a repo, parsed and stitched.
+pub(super) fn transcript_files_for_repo(repo_path: &str) -> Vec<TranscriptFile> {
+    match claude_projects_dir() {
+        Some(claude_projects) => transcript_files_in(&claude_projects, repo_path),
+        None => vec![],
+    }
+}
+
+/// Transcripts under `claude_projects` recorded in `repo_path` or below it.
+fn transcript_files_in(claude_projects: &Path, repo_path: &str) -> Vec<TranscriptFile> {
+    let mut files: Vec<TranscriptFile> = Vec::new();
+
+    for project_dir in candidate_project_dirs(claude_projects, repo_path) {
+        // Read all .jsonl files in the project directory
+        let entries = match fs::read_dir(&project_dir) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+
+        let transcripts: Vec<Transcript> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("jsonl"))
+            .filter_map(|path| load_transcript(&path))
+            .filter(|transcript| transcript.belongs_to(repo_path))
+            .collect();
+
+        files.append(&mut stitch_transcripts(transcripts));
+    }
+
+    files
+}
+
+/// One JSONL transcript loaded into memory, with the message links needed to
+/// stitch resumed sessions back onto the session they continue.
+struct Transcript {
+    path: PathBuf,
+    /// File stem — the session id Claude Code gave this transcript
+    id: String,
+    entries: Vec<Value>,
+    uuids: HashSet<String>,
+    /// `parentUuid`s pointing outside this transcript and `leafUuid`s of its
+    /// `summary` entries: the messages this transcript continues from
+    continues_from: Vec<String>,
+    first_timestamp: String,
+    last_timestamp: String,
+    /// Working directory of the first entry that records one
+    cwd: Option<String>,
+}
+
+impl Transcript {
+    /// Whether the transcript was recorded in `repo_path` or a directory below
+    /// it. A transcript without any `cwd` is only trusted in the repo's own
+    /// project directory.
+    fn belongs_to(&self, repo_path: &str) -> bool {
+        let repo_path = repo_path.trim_end_matches('/');
+        match &self.cwd {
+            Some(cwd) => Path::new(cwd).starts_with(repo_path),
+            None => {
+                let dir_name = self.path.parent().and_then(|p| p.file_name());
+                dir_name.and_then(|n| n.to_str()) == Some(&repo_path.replace('/', "-"))
+            }
+        }
     }
+}
+
+fn load_transcript(path: &Path) -> Option<Transcript> {
+    let content = fs::read_to_string(path).ok()?;
+    let id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    Some(read_transcript(path.to_path_buf(), id, &content))
+}
+
+/// Index the JSONL `content` of the transcript `id`.
+fn read_transcript(path: PathBuf, id: String, content: &str) -> Transcript {
+    let entries: Vec<Value> = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
 
-    // Encode the repo path the way Claude Code does: replace / with -
-    let encoded = repo_path.replace('/', "-");
+    let uuids: HashSet<String> = entries
+        .iter()
+        .filter_map(|e| e.get("uuid").and_then(|u| u.as_str()))
+        .map(|u| u.to_string())
+        .collect();
 
-    let project_dir = claude_projects.join(&encoded);
-    if project_dir.exists() {
-        return Some(project_dir);
+    let mut continues_from: Vec<String> = Vec::new();
+    for entry in &entries {
+        if entry.get("type").and_then(|t| t.as_str()) == Some("summary") {
+            if let Some(leaf) = entry.get("leafUuid").and_then(|u| u.as_str()) {
+                continues_from.push(leaf.to_string());
+            }
+        } else if let Some(parent) = entry.get("parentUuid").and_then(|u| u.as_str()) {
+            if !uuids.contains(parent) {
+                continues_from.push(parent.to_string());
+            }
+        }
     }
 
-    // Also try without trailing slash variations
-    let trimmed = repo_path.trim_end_matches('/');
-    let encoded_trimmed = trimmed.replace('/', "-");
-    let project_dir_trimmed = claude_projects.join(&encoded_trimmed);
-    if project_dir_trimmed.exists() {
-        return Some(project_dir_trimmed);
+    // Lines aren't always in time order, so take the earliest and latest timestamps
+    let timestamps = entries
+        .iter()
+        .filter_map(|e| e.get("timestamp").and_then(|t| t.as_str()));
+    let first_timestamp = timestamps.clone().min().unwrap_or_default().to_string();
+    let last_timestamp = timestamps.max().unwrap_or_default().to_string();
+    let cwd = entries
+        .iter()
+        .find_map(|e| e.get("cwd").and_then(|c| c.as_str()))
+        .map(|c| c.to_string());
+
+    Transcript {
+        path,
+        id,
+        entries,
+        uuids,
+        continues_from,
+        first_timestamp,
+        last_timestamp,
+        cwd,
     }
+}
 
-    None
+/// Parse the JSONL `content` of one transcript on its own, attributing its
+/// prompts to `session_id`.
+pub(super) fn parse_transcript(
+    transcript_id: &str,
+    content: &str,
+    session_id: &str,
+) -> Vec<PromptSession> {
+    let transcript = read_transcript(PathBuf::new(), transcript_id.to_string(), content);
+    parse_entries(&transcript.entries, session_id, &transcript.id, &mut HashSet::new()).sessions
 }
 
-/// Parse all Claude Code session JSONL files for a given repo path.
-/// Returns a list of PromptSession — one per user prompt found.
-pub fn parse_sessions_for_repo(repo_path: &str) -> Vec<PromptSession> {
-    let project_dir = match find_claude_project_dir(repo_path) {
-        Some(d) => d,
-        None => {
-            log::info!(
-                "No Claude Code project directory found for: {}",
-                repo_path
-            );
-            return vec![];
+/// Stitch and parse the transcripts of one Claude Code project directory.
+///
+/// A resumed session is written to a new file that links back to the original
+/// through `parentUuid`/`leafUuid` and often repeats its messages. Such a
+/// transcript is stitched onto the session it continues: its prompts keep the
+/// original session id, and messages already seen in an earlier transcript are
+/// counted once. Work it logs before its first new prompt is added to the last
+/// prompt stitched so far for that session.
+fn stitch_transcripts(mut transcripts: Vec<Transcript>) -> Vec<TranscriptFile> {
+    // A resumed transcript repeats the original's history, so ties on the first
+    // timestamp are broken by which one runs longer
+    transcripts.sort_by(|a, b| {
+        (&a.first_timestamp, &a.last_timestamp).cmp(&(&b.first_timestamp, &b.last_timestamp))
+    });
+
+    // uuid → index of the earliest transcript that recorded it
+    let mut owner: HashMap<&str, usize> = HashMap::new();
+    for (i, transcript) in transcripts.iter().enumerate() {
+        for uuid in &transcript.uuids {
+            owner.entry(uuid.as_str()).or_insert(i);
         }
+    }
+
+    // Transcripts are sorted oldest first, so a parent is resolved before its continuations
+    let mut root_ids: Vec<String> = Vec::with_capacity(transcripts.len());
+    for (i, transcript) in transcripts.iter().enumerate() {
+        let parent = transcript
+            .continues_from
+            .iter()
+            .chain(transcript.uuids.iter())
+            .filter_map(|uuid| owner.get(uuid.as_str()).copied())
+            .filter(|&j| j < i)
+            .min();
+
+        root_ids.push(match parent {
+            Some(j) => root_ids[j].clone(),
+            None => transcript.id.clone(),
+        });
+    }
+
+    let mut seen_uuids: HashSet<String> = HashSet::new();
+    // Logical session id → (file, prompt) index of its last stitched prompt
+    let mut last_prompt: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut files: Vec<TranscriptFile> = Vec::with_capacity(transcripts.len());
+    for (transcript, root_id) in transcripts.iter().zip(root_ids) {
+        let parsed = parse_entries(&transcript.entries, &root_id, &transcript.id, &mut seen_uuids);
+
+        if let Some(work) = parsed.continued_work {
+            if let Some(&(file, prompt)) = last_prompt.get(&root_id) {
+                add_continued_work(&mut files[file].sessions[prompt], work);
+            }
+        }
+        if !parsed.sessions.is_empty() {
+            last_prompt.insert(root_id.clone(), (files.len(), parsed.sessions.len() - 1));
+        }
+
+        files.push(TranscriptFile {
+            path: transcript.path.clone(),
+            transcript_id: transcript.id.clone(),
+            sessions: parsed.sessions,
+            session_id: root_id,
+            first_timestamp: transcript.first_timestamp.clone(),
+            last_timestamp: transcript.last_timestamp.clone(),
+        });
+    }
+    files
+}
+
+/// Add the work a resumed transcript logged before its first prompt to the
+/// prompt it continues.
+fn add_continued_work(prompt: &mut PromptSession, work: PromptSession) {
+    prompt.token_usage.input_tokens += work.token_usage.input_tokens;
+    prompt.token_usage.output_tokens += work.token_usage.output_tokens;
+    prompt.token_usage.cache_read_tokens += work.token_usage.cache_read_tokens;
+    prompt.token_usage.cache_creation_tokens += work.token_usage.cache_creation_tokens;
+    prompt.tool_call_count += work.tool_call_count;
+    for (files, more) in [
+        (&mut prompt.files_touched, work.files_touched),
+        (&mut prompt.files_written, work.files_written),
+    ] {
+        files.extend(more);
+        files.sort();
+        files.dedup();
+    }
+    prompt.model = prompt.model.take().or(work.model);
+    prompt.time_end = prompt.time_end.take().max(work.time_end);
+    if work.assistant_reply.is_some() {
+        prompt.assistant_reply = work.assistant_reply;
+    }
+    prompt.tool_errors.extend(work.tool_errors);
+    prompt.interrupted |= work.interrupted;
+    prompt.permission_denials.extend(work.permission_denials);
+    prompt.stop_reasons.extend(work.stop_reasons);
+}
+
+/// What a `type: "user"` transcript line represents. Only `Prompt` starts a new
+/// PromptSession; everything else is either folded into the current prompt or ignored.
+#[derive(Debug, PartialEq)]
+enum UserMessageKind {
+    /// Text typed by the developer, including custom slash commands
+    Prompt(String),
+    /// tool_result blocks returned to the model
+    ToolResult,
+    /// "[Request interrupted by user]"
+    Interrupt,
+    /// Built-in slash command such as /compact, /clear or /model
+    BuiltinCommand,
+    /// `<local-command-stdout>` wrapper echoing a command's output
+    CommandOutput,
+    /// Conversation summary injected by /compact or auto-compaction
+    CompactSummary,
+    /// Hook output, caveats, expanded command bodies, sidechain (sub-agent) prompts
+    Meta,
+    Empty,
+}
+
+/// Built-in Claude Code slash commands that don't ask for any work.
+const BUILTIN_COMMANDS: &[&str] = &[
+    "add-dir", "agents", "bashes", "bug", "clear", "compact", "config", "context", "cost",
+    "doctor", "exit", "export", "help", "hooks", "ide", "install-github-app", "login",
+    "logout", "mcp", "memory", "migrate-installer", "model", "output-style", "permissions",
+    "privacy-settings", "release-notes", "resume", "rewind", "status", "statusline",
+    "terminal-setup", "todos", "upgrade", "usage", "vim",
+];
+
+/// Start of the user message Claude Code injects after compacting a conversation.
+const COMPACT_SUMMARY_PREFIX: &str =
+    "This session is being continued from a previous conversation";
+
+/// Text between `<tag>` and `</tag>`, if present.
+fn tag_content<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = text.find(&open)? + open.len();
+    let end = text[start..].find(&close)? + start;
+    Some(&text[start..end])
+}
+
+fn classify_user_message(value: &Value) -> UserMessageKind {
+    let flag = |name: &str| value.get(name).and_then(|f| f.as_bool()).unwrap_or(false);
+
+    let content = match value.get("message").and_then(|m| m.get("content")) {
+        Some(c) => c,
+        None => return UserMessageKind::Empty,
     };
 
-    let mut all_sessions: Vec<PromptSession> = Vec::new();
+    if flag("isCompactSummary") {
+        return UserMessageKind::CompactSummary;
+    }
 
-    // Read all .jsonl files in the project directory
-    let entries = match fs::read_dir(&project_dir) {
-        Ok(e) => e,
-        Err(_) => return vec![],
+    let text = match content {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => {
+            if items
+                .iter()
+                .any(|item| item.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+            {
+                return UserMessageKind::ToolResult;
+            }
+            // A prompt with structured content (e.g., with images): keep the text blocks
+            items
+                .iter()
+                .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<&str>>()
+                .join("\n")
+        }
+        _ => return UserMessageKind::Empty,
     };
 
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
-            let session_id = path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown")
-                .to_string();
-
-            let mut sessions = parse_session_file(&path, &session_id);
-            all_sessions.append(&mut sessions);
+    if is_interrupt(&text) {
+        return UserMessageKind::Interrupt;
+    }
+    if flag("isMeta") || flag("isSidechain") {
+        return UserMessageKind::Meta;
+    }
+
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return UserMessageKind::Empty;
+    }
+    if trimmed.starts_with("<local-command-stdout>") || trimmed.starts_with("<local-command-stderr>")
+    {
+        return UserMessageKind::CommandOutput;
+    }
+    if trimmed.starts_with("<local-command-caveat>")
+        || trimmed.starts_with("<user-prompt-submit-hook>")
+    {
+        return UserMessageKind::Meta;
+    }
+    if trimmed.starts_with(COMPACT_SUMMARY_PREFIX) {
+        return UserMessageKind::CompactSummary;
+    }
+
+    if let Some(name) = tag_content(trimmed, "command-name") {
+        let name = name.trim().trim_start_matches('/');
+        if BUILTIN_COMMANDS.contains(&name) {
+            return UserMessageKind::BuiltinCommand;
         }
+        let args = tag_content(trimmed, "command-args").unwrap_or("").trim();
+        return UserMessageKind::Prompt(if args.is_empty() {
+            format!("/{}", name)
+        } else {
+            format!("/{} {}", name, args)
+        });
     }
 
-    // Sort by timestamp (newest first)
-    all_sessions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    all_sessions
+    UserMessageKind::Prompt(text)
 }
 
-/// Parse a single JSONL session file into PromptSession entries.
+/// Prompts parsed from the entries of one transcript.
+struct ParsedEntries {
+    sessions: Vec<PromptSession>,
+    /// Assistant work logged before the first prompt; in a resumed transcript it
+    /// continues the last prompt of the earlier one
+    continued_work: Option<PromptSession>,
+}
+
+impl ParsedEntries {
+    fn finish(&mut self, builder: PromptBuilder, session_id: &str, transcript_id: &str) {
+        // Only the builder opened before the first prompt has no prompt text
+        if !builder.prompt_text.is_empty() {
+            self.sessions.push(builder.build(session_id, transcript_id));
+        } else if builder.has_activity() {
+            self.continued_work = Some(builder.build(session_id, transcript_id));
+        }
+    }
+}
+
+/// Turn transcript entries into PromptSession entries.
 /// Each user prompt becomes one PromptSession, accumulating all tool calls
 /// and file touches that happen between it and the next user prompt.
-fn parse_session_file(path: &Path, session_id: &str) -> Vec<PromptSession> {
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => return vec![],
+/// Entries whose `uuid` is already in `seen_uuids` (copied from a resumed
+/// session) are skipped.
+fn parse_entries(
+    entries: &[Value],
+    session_id: &str,
+    transcript_id: &str,
+    seen_uuids: &mut HashSet<String>,
+) -> ParsedEntries {
+    let mut parsed = ParsedEntries {
+        sessions: Vec::new(),
+        continued_work: None,
     };
+    let mut current_prompt = Some(PromptBuilder::new(String::new(), String::new(), None));
 
-    let mut sessions: Vec<PromptSession> = Vec::new();
-    let mut current_prompt: Option<PromptBuilder> = None;
-
-    for line in content.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+    for value in entries {
+        if let Some(uuid) = value.get("uuid").and_then(|u| u.as_str()) {
+            if !seen_uuids.insert(uuid.to_string()) {
+                continue;
+            }
         }
 
-        let value: Value = match serde_json::from_str(line) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
         let msg_type = value.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        let cwd = value
+            .get("cwd")
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string());
 
         match msg_type {
             "user" => {
-                let message = match value.get("message") {
-                    Some(m) => m,
-                    None => continue,
-                };
-
-                let content = message.get("content");
                 let timestamp = value
                     .get("timestamp")
                     .and_then(|t| t.as_str())
                     .unwrap_or("")
                     .to_string();
 
-                // Determine if this is a real user prompt or a tool result
-                if let Some(content_val) = content {
-                    if content_val.is_string() {
+                match classify_user_message(value) {
+                    UserMessageKind::Prompt(prompt_text) => {
                         // Real user prompt — finalize previous prompt and start a new one
                         if let Some(builder) = current_prompt.take() {
-                            sessions.push(builder.build(session_id));
+                            parsed.finish(builder, session_id, transcript_id);
                         }
-
-                        let prompt_text = content_val.as_str().unwrap_or("").to_string();
-
-                        // Skip empty prompts
-                        if prompt_text.trim().is_empty() {
-                            continue;
-                        }
-
-                        current_prompt = Some(PromptBuilder {
-                            prompt_text,
-                            timestamp,
-                            time_end: None,
-                            files_touched: HashSet::new(),
-                            files_written: HashSet::new(),
-                            tool_call_count: 0,
-                            model: None,
-                            input_tokens: 0,
-                            output_tokens: 0,
-                            cache_read_tokens: 0,
-                        });
-                    } else if content_val.is_array() {
-                        // Check if it's a user prompt with array content (image blocks, etc.)
-                        // vs a tool result
-                        let arr = content_val.as_array().unwrap();
-                        let is_tool_result = arr
-                            .iter()
-                            .any(|item| item.get("type").and_then(|t| t.as_str()) == Some("tool_result"));
-
-                        if !is_tool_result {
-                            // This is a user prompt with structured content (e.g., with images)
-                            // Extract text from text blocks
-                            let prompt_text: String = arr
-                                .iter()
-                                .filter_map(|item| {
-                                    if item.get("type").and_then(|t| t.as_str()) == Some("text") {
-                                        item.get("text").and_then(|t| t.as_str())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<Vec<&str>>()
-                                .join("\n");
-
-                            if !prompt_text.trim().is_empty() {
-                                if let Some(builder) = current_prompt.take() {
-                                    sessions.push(builder.build(session_id));
+                        current_prompt = Some(PromptBuilder::new(prompt_text, timestamp, cwd));
+                    }
+                    UserMessageKind::ToolResult => {
+                        if let Some(ref mut builder) = current_prompt {
+                            if let Some(items) = value
+                                .get("message")
+                                .and_then(|m| m.get("content"))
+                                .and_then(|c| c.as_array())
+                            {
+                                for item in items {
+                                    builder.record_tool_result(item);
                                 }
-
-                                current_prompt = Some(PromptBuilder {
-                                    prompt_text,
-                                    timestamp: timestamp.clone(),
-                                    time_end: None,
-                                    files_touched: HashSet::new(),
-                                    files_written: HashSet::new(),
-                                    tool_call_count: 0,
-                                    model: None,
-                                    input_tokens: 0,
-                                    output_tokens: 0,
-                                    cache_read_tokens: 0,
-                                });
                             }
-                        }
-                        // tool_result lines: just update time_end
-                        if let Some(ref mut builder) = current_prompt {
                             if !timestamp.is_empty() {
                                 builder.time_end = Some(timestamp);
                             }
                         }
                     }
+                    // An interrupt is logged as a user message but ends the current
+                    // prompt rather than starting a new one
+                    UserMessageKind::Interrupt => {
+                        if let Some(ref mut builder) = current_prompt {
+                            builder.mark_interrupted(&timestamp);
+                        }
+                    }
+                    UserMessageKind::BuiltinCommand
+                    | UserMessageKind::CommandOutput
+                    | UserMessageKind::CompactSummary
+                    | UserMessageKind::Meta
+                    | UserMessageKind::Empty => {}
                 }
             }
             "assistant" => {
@@ -218,33 +557,62 @@ fn parse_session_file(path: &Path, session_id: &str) -> Vec<PromptSession> {
                         }
                     }
 
-                    // Extract token usage
+                    let response_key = message
+                        .get("id")
+                        .and_then(|id| id.as_str())
+                        .or_else(|| value.get("requestId").and_then(|id| id.as_str()));
+
+                    if let Some(reason) = message.get("stop_reason").and_then(|r| r.as_str()) {
+                        builder.record_stop_reason(response_key, reason);
+                    }
+
+                    // Extract token usage. While streaming, Claude Code writes one line per
+                    // content block, each repeating the usage of the same API response, so
+                    // usage is keyed by message id (or request id) and counted once.
                     if let Some(usage) = message.get("usage") {
-                        builder.input_tokens += usage
-                            .get("input_tokens")
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0);
-                        builder.output_tokens += usage
-                            .get("output_tokens")
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0);
-                        builder.cache_read_tokens += usage
-                            .get("cache_read_input_tokens")
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0);
+                        let line_usage = TokenUsage {
+                            input_tokens: usage_field(usage, "input_tokens"),
+                            output_tokens: usage_field(usage, "output_tokens"),
+                            cache_read_tokens: usage_field(usage, "cache_read_input_tokens"),
+                            cache_creation_tokens: usage_field(usage, "cache_creation_input_tokens"),
+                        };
+
+                        match response_key {
+                            Some(key) => builder.record_usage(key, line_usage),
+                            None => builder.unkeyed_usage.push(line_usage),
+                        }
                     }
 
                     // Extract tool calls from content array
                     if let Some(content_arr) = message.get("content").and_then(|c| c.as_array()) {
                         for block in content_arr {
-                            if block.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
-                                continue;
+                            match block.get("type").and_then(|t| t.as_str()) {
+                                Some("tool_use") => {}
+                                Some("text") => {
+                                    if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                                        builder.record_reply_text(response_key, text);
+                                    }
+                                    continue;
+                                }
+                                _ => continue,
+                            }
+
+                            // The same tool_use block can be re-emitted by a later chunk
+                            if let Some(tool_id) = block.get("id").and_then(|i| i.as_str()) {
+                                if !builder.tool_use_ids.insert(tool_id.to_string()) {
+                                    continue;
+                                }
                             }
 
                             builder.tool_call_count += 1;
 
                             let tool_name =
                                 block.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                            if let Some(tool_id) = block.get("id").and_then(|i| i.as_str()) {
+                                builder
+                                    .tool_names
+                                    .insert(tool_id.to_string(), tool_name.to_string());
+                            }
                             let input = block.get("input");
 
                             match tool_name {
@@ -294,17 +662,49 @@ fn parse_session_file(path: &Path, session_id: &str) -> Vec<PromptSession> {
                 }
             }
             _ => {
-                // file-history-snapshot, progress, etc. — skip
+                // summary, file-history-snapshot, system, progress, etc. — skip
             }
         }
     }
 
     // Finalize last prompt
     if let Some(builder) = current_prompt.take() {
-        sessions.push(builder.build(session_id));
+        parsed.finish(builder, session_id, transcript_id);
     }
 
-    sessions
+    parsed
+}
+
+/// Claude Code logs "[Request interrupted by user]" (or "... for tool use]")
+/// as a user message when the developer presses Esc.
+const INTERRUPT_MARKER: &str = "[Request interrupted by user";
+
+/// Tool result text written when the developer rejects a permission prompt.
+const PERMISSION_DENIED_MARKER: &str = "The user doesn't want to proceed with this tool use";
+
+/// Longest tool error message kept per error.
+const MAX_TOOL_ERROR_LEN: usize = 500;
+
+fn is_interrupt(text: &str) -> bool {
+    text.trim_start().starts_with(INTERRUPT_MARKER)
+}
+
+/// Flatten a tool_result `content` (plain string or list of text blocks) into text.
+fn tool_result_text(item: &Value) -> String {
+    match item.get("content") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<&str>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Read a token count from an API `usage` object.
+fn usage_field(usage: &Value, field: &str) -> u64 {
+    usage.get(field).and_then(|v| v.as_u64()).unwrap_or(0)
 }
 
 /// Builder to accumulate data for a single prompt session
@@ -315,21 +715,168 @@ struct PromptBuilder {
     files_touched: HashSet<String>,
     files_written: HashSet<String>,
     tool_call_count: u32,
+    tool_use_ids: HashSet<String>,
     model: Option<String>,
-    input_tokens: u64,
-    output_tokens: u64,
-    cache_read_tokens: u64,
+    /// Usage per API response (message id or request id), deduplicated across streamed chunks
+    usage_by_response: HashMap<String, TokenUsage>,
+    /// Usage from lines that carry no message or request id
+    unkeyed_usage: Vec<TokenUsage>,
+    cwd: Option<String>,
+    /// tool_use id → tool name, to name the tool behind a tool_result
+    tool_names: HashMap<String, String>,
+    /// Response key and text of the latest assistant response that contained text
+    reply_key: Option<String>,
+    reply_text: String,
+    tool_errors: Vec<ToolError>,
+    interrupted: bool,
+    permission_denials: Vec<String>,
+    /// Stop reason per API response, in order
+    stop_reasons: Vec<(Option<String>, String)>,
 }
 
 impl PromptBuilder {
-    fn build(self, session_id: &str) -> PromptSession {
+    fn new(prompt_text: String, timestamp: String, cwd: Option<String>) -> Self {
+        Self {
+            prompt_text,
+            timestamp,
+            time_end: None,
+            files_touched: HashSet::new(),
+            files_written: HashSet::new(),
+            tool_call_count: 0,
+            tool_use_ids: HashSet::new(),
+            model: None,
+            usage_by_response: HashMap::new(),
+            unkeyed_usage: Vec::new(),
+            cwd,
+            tool_names: HashMap::new(),
+            reply_key: None,
+            reply_text: String::new(),
+            tool_errors: Vec::new(),
+            interrupted: false,
+            permission_denials: Vec::new(),
+            stop_reasons: Vec::new(),
+        }
+    }
+
+    /// Whether anything was recorded since the builder was opened.
+    fn has_activity(&self) -> bool {
+        self.time_end.is_some()
+            || self.tool_call_count > 0
+            || !self.usage_by_response.is_empty()
+            || !self.unkeyed_usage.is_empty()
+            || self.interrupted
+    }
+
+    fn mark_interrupted(&mut self, timestamp: &str) {
+        self.interrupted = true;
+        if !timestamp.is_empty() {
+            self.time_end = Some(timestamp.to_string());
+        }
+    }
+
+    /// Keep the text of the latest assistant response: text from a new response
+    /// replaces the reply, text from another chunk of the same response extends it.
+    fn record_reply_text(&mut self, response_key: Option<&str>, text: &str) {
+        let same_response =
+            response_key.is_some() && self.reply_key.as_deref() == response_key;
+        if same_response {
+            if self.reply_text.contains(text) {
+                return;
+            }
+            self.reply_text.push('\n');
+            self.reply_text.push_str(text);
+        } else {
+            self.reply_key = response_key.map(|k| k.to_string());
+            self.reply_text = text.to_string();
+        }
+    }
+
+    fn record_stop_reason(&mut self, response_key: Option<&str>, reason: &str) {
+        let key = response_key.map(|k| k.to_string());
+        if key.is_some() {
+            if let Some(existing) = self.stop_reasons.iter_mut().find(|(k, _)| *k == key) {
+                existing.1 = reason.to_string();
+                return;
+            }
+        }
+        self.stop_reasons.push((key, reason.to_string()));
+    }
+
+    /// Record a failed or rejected tool call from a tool_result block.
+    fn record_tool_result(&mut self, item: &Value) {
+        if item.get("type").and_then(|t| t.as_str()) != Some("tool_result") {
+            return;
+        }
+        let is_error = item.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false);
+        if !is_error {
+            return;
+        }
+
+        let tool_use_id = item
+            .get("tool_use_id")
+            .and_then(|i| i.as_str())
+            .unwrap_or("")
+            .to_string();
+        let tool_name = self
+            .tool_names
+            .get(&tool_use_id)
+            .cloned()
+            .unwrap_or_default();
+        let text = tool_result_text(item);
+
+        if text.contains(PERMISSION_DENIED_MARKER) {
+            self.permission_denials.push(tool_name);
+            return;
+        }
+
+        let message = if text.len() > MAX_TOOL_ERROR_LEN {
+            let mut end = MAX_TOOL_ERROR_LEN;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}...", &text[..end])
+        } else {
+            text
+        };
+
+        self.tool_errors.push(ToolError {
+            tool_name,
+            tool_use_id,
+            message,
+        });
+    }
+
+    /// Record the usage reported by one chunk of a response. Chunks of the same
+    /// response repeat the input/cache counts and grow output_tokens, so the
+    /// largest value seen per field is the response's final usage.
+    fn record_usage(&mut self, response_key: &str, usage: TokenUsage) {
+        let entry = self
+            .usage_by_response
+            .entry(response_key.to_string())
+            .or_default();
+        entry.input_tokens = entry.input_tokens.max(usage.input_tokens);
+        entry.output_tokens = entry.output_tokens.max(usage.output_tokens);
+        entry.cache_read_tokens = entry.cache_read_tokens.max(usage.cache_read_tokens);
+        entry.cache_creation_tokens = entry.cache_creation_tokens.max(usage.cache_creation_tokens);
+    }
+
+    fn build(self, session_id: &str, transcript_id: &str) -> PromptSession {
         let mut files_touched: Vec<String> = self.files_touched.into_iter().collect();
         files_touched.sort();
         let mut files_written: Vec<String> = self.files_written.into_iter().collect();
         files_written.sort();
 
+        let mut token_usage = TokenUsage::default();
+        for usage in self.usage_by_response.values().chain(self.unkeyed_usage.iter()) {
+            token_usage.input_tokens += usage.input_tokens;
+            token_usage.output_tokens += usage.output_tokens;
+            token_usage.cache_read_tokens += usage.cache_read_tokens;
+            token_usage.cache_creation_tokens += usage.cache_creation_tokens;
+        }
+
         PromptSession {
             session_id: session_id.to_string(),
+            transcript_id: transcript_id.to_string(),
             prompt_text: self.prompt_text,
             timestamp: self.timestamp,
             associated_commit_hashes: vec![], // populated during correlation
@@ -337,53 +884,246 @@ impl PromptBuilder {
             similarity_score: 0.0,
             scope_match: 0.0,
             intent: None,
+            intent_details: None,
             files_touched,
             files_written,
             tool_call_count: self.tool_call_count,
             model: self.model,
-            token_usage: TokenUsage {
-                input_tokens: self.input_tokens,
-                output_tokens: self.output_tokens,
-                cache_read_tokens: self.cache_read_tokens,
-            },
+            token_usage,
             time_end: self.time_end,
+            cwd: self.cwd,
+            cost_usd: 0.0, // priced during scan
+            assistant_reply: if self.reply_text.is_empty() {
+                None
+            } else {
+                Some(self.reply_text)
+            },
+            tool_errors: self.tool_errors,
+            interrupted: self.interrupted,
+            permission_denials: self.permission_denials,
+            stop_reasons: self.stop_reasons.into_iter().map(|(_, r)| r).collect(),
         }
     }
 }
 
-/// Tauri command: parse Claude Code sessions for a repo and return prompt sessions.
+/// Tauri command: parse Claude Code sessions for a repo and return prompt
+/// sessions, redacted with the repo's settings like a scan's.
 #[tauri::command]
-pub async fn get_sessions(path: String) -> Result<Vec<PromptSession>, String> {
-    Ok(parse_sessions_for_repo(&path))
+pub async fn get_sessions(path: String) -> Result<SessionsResult, String> {
+    let redactor = project_redactor(Path::new(&path))?;
+    let mut sessions = parse_sessions_for_repo(&path);
+    let redaction = redactor.redact_sessions(&mut sessions);
+    Ok(SessionsResult {
+        sessions,
+        redaction,
+    })
 }
 
-/// Tauri command: delete all Claude Code session JSONL files for a repo.
-#[tauri::command]
-pub async fn delete_sessions(path: String) -> Result<u32, String> {
-    let project_dir = match find_claude_project_dir(&path) {
-        Some(d) => d,
-        None => return Ok(0),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let entries = match fs::read_dir(&project_dir) {
-        Ok(e) => e,
-        Err(_) => return Ok(0),
-    };
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("sessions")
+            .join(name)
+    }
 
-    let mut deleted = 0u32;
-    for entry in entries.flatten() {
-        let entry_path = entry.path();
-        if entry_path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
-            if fs::remove_file(&entry_path).is_ok() {
-                deleted += 1;
-            }
-            // Also remove the corresponding session directory if it exists
-            let session_dir = entry_path.with_extension("");
-            if session_dir.is_dir() {
-                let _ = fs::remove_dir_all(&session_dir);
-            }
-        }
+    /// Parse `<name>.jsonl` as the transcript `name`.
+    fn parse_fixture(name: &str, session_id: &str) -> Vec<PromptSession> {
+        let content = fs::read_to_string(fixture(&format!("{}.jsonl", name)))
+            .expect("fixture should load");
+        parse_transcript(name, &content, session_id)
     }
 
-    Ok(deleted)
+    #[test]
+    fn streamed_chunks_are_counted_once_per_response() {
+        let sessions = parse_fixture("streamed_multi_chunk", "s1");
+        assert_eq!(sessions.len(), 2);
+
+        // msg_01A + msg_01B + msg_01C, each counted with its final chunk's usage
+        let first = &sessions[0];
+        assert_eq!(first.prompt_text, "Add a retry to the payment webhook handler");
+        assert_eq!(first.token_usage.input_tokens, 4 + 6 + 5);
+        assert_eq!(first.token_usage.output_tokens, 96 + 212 + 14);
+        assert_eq!(first.token_usage.cache_creation_tokens, 2100 + 400 + 250);
+        assert_eq!(first.token_usage.cache_read_tokens, 13500 + 15600 + 16000);
+        assert_eq!(first.tool_call_count, 2);
+        assert_eq!(
+            first.files_written,
+            vec!["/Users/dev/shop/src/webhooks/payment.rs".to_string()]
+        );
+
+        let second = &sessions[1];
+        assert_eq!(second.token_usage.input_tokens, 8);
+        assert_eq!(second.token_usage.output_tokens, 75);
+        assert_eq!(second.token_usage.cache_creation_tokens, 300);
+        assert_eq!(second.token_usage.cache_read_tokens, 16250);
+        assert_eq!(second.tool_call_count, 1);
+    }
+
+    #[test]
+    fn records_tool_errors_denials_and_interrupts() {
+        let sessions = parse_fixture("interrupted_with_tool_errors", "s3");
+        assert_eq!(sessions.len(), 2);
+
+        let failed = &sessions[0];
+        assert!(failed.interrupted);
+        assert_eq!(failed.tool_errors.len(), 1);
+        assert_eq!(failed.tool_errors[0].tool_name, "Bash");
+        assert!(failed.tool_errors[0].message.contains("already exists"));
+        assert_eq!(failed.permission_denials, vec!["Bash".to_string()]);
+        assert_eq!(failed.stop_reasons, vec!["tool_use", "tool_use"]);
+        assert_eq!(
+            failed.assistant_reply.as_deref(),
+            Some("The orders table already exists. I'll drop and recreate the dev database.")
+        );
+
+        let completed = &sessions[1];
+        assert!(!completed.interrupted);
+        assert!(completed.tool_errors.is_empty());
+        assert_eq!(completed.stop_reasons, vec!["tool_use", "end_turn"]);
+        assert_eq!(
+            completed.assistant_reply.as_deref(),
+            Some("Added migration 0007, which only creates the orders table when it is missing.")
+        );
+    }
+
+    #[test]
+    fn only_genuine_prompts_become_sessions() {
+        let sessions = parse_fixture("commands_and_compaction", "s4");
+        let prompts: Vec<&str> = sessions.iter().map(|s| s.prompt_text.as_str()).collect();
+        // /model, its stdout, the caveat, /compact, the compact summary and the
+        // expanded /review body are all dropped; the custom /review command is kept
+        assert_eq!(
+            prompts,
+            vec!["Extract the tax calculation into its own module", "/review src/tax.rs"]
+        );
+        assert_eq!(sessions[1].token_usage.output_tokens, 30);
+        assert_eq!(
+            sessions[1].assistant_reply.as_deref(),
+            Some("The module looks good; consider rounding.")
+        );
+    }
+
+    #[test]
+    fn resumed_sessions_are_stitched_and_not_double_counted() {
+        let files = stitch_transcripts(
+            ["resumed_continuation.jsonl", "resumed_original.jsonl"]
+                .iter()
+                .filter_map(|name| load_transcript(&fixture(name)))
+                .collect(),
+        );
+        assert!(files.iter().all(|f| f.session_id == "resumed_original"));
+
+        let sessions: Vec<PromptSession> = files.into_iter().flat_map(|f| f.sessions).collect();
+        assert_eq!(sessions.len(), 2);
+
+        assert_eq!(sessions[0].prompt_text, "Add pagination to the orders endpoint");
+        assert_eq!(sessions[0].transcript_id, "resumed_original");
+        assert_eq!(sessions[0].token_usage.output_tokens, 60);
+
+        assert_eq!(sessions[1].prompt_text, "Also return the total count");
+        assert_eq!(sessions[1].session_id, "resumed_original");
+        assert_eq!(sessions[1].transcript_id, "resumed_continuation");
+    }
+
+    #[test]
+    fn continued_work_without_a_prompt_is_added_to_the_last_prompt() {
+        let files = stitch_transcripts(
+            ["resumed_tail.jsonl", "resumed_original.jsonl"]
+                .iter()
+                .filter_map(|name| load_transcript(&fixture(name)))
+                .collect(),
+        );
+        assert_eq!(files[1].transcript_id, "resumed_tail");
+        assert!(files[1].sessions.is_empty());
+
+        let prompt = &files[0].sessions[0];
+        assert_eq!(prompt.transcript_id, "resumed_original");
+        assert_eq!(prompt.token_usage.output_tokens, 60 + 40 + 20);
+        assert_eq!(prompt.token_usage.cache_read_tokens, 7000 + 7050 + 7100);
+        assert_eq!(prompt.tool_call_count, 2);
+        assert_eq!(
+            prompt.files_written,
+            ["/Users/dev/shop/src/api/mod.rs", "/Users/dev/shop/src/api/orders.rs"]
+        );
+        assert_eq!(prompt.stop_reasons, ["tool_use", "tool_use", "end_turn"]);
+        assert_eq!(prompt.time_end.as_deref(), Some("2025-10-05T09:30:06.000Z"));
+        assert_eq!(
+            prompt.assistant_reply.as_deref(),
+            Some("Pagination is in place: the orders endpoint takes page and per_page.")
+        );
+    }
+
+    #[test]
+    fn archived_copies_of_stitched_prompts_are_counted_once() {
+        let prompt = |transcript_id: &str, timestamp: &str| PromptSession {
+            session_id: "resumed_original".to_string(),
+            transcript_id: transcript_id.to_string(),
+            timestamp: timestamp.to_string(),
+            ..Default::default()
+        };
+        let mut sessions = vec![prompt("resumed_original", "2025-10-05T09:00:00.000Z")];
+        // The continuation was deleted after repeating the original's first prompt,
+        // and is also in a second snapshot
+        let archived = vec![
+            prompt("resumed_continuation", "2025-10-05T09:00:00.000Z"),
+            prompt("resumed_continuation", "2025-10-06T11:00:00.000Z"),
+            prompt("resumed_continuation", "2025-10-06T11:00:00.000Z"),
+        ];
+
+        merge_archived(&mut sessions, archived);
+        let timestamps: Vec<&str> = sessions.iter().map(|s| s.timestamp.as_str()).collect();
+        assert_eq!(timestamps, ["2025-10-05T09:00:00.000Z", "2025-10-06T11:00:00.000Z"]);
+        assert_eq!(sessions[0].transcript_id, "resumed_original");
+    }
+
+    #[test]
+    fn sibling_repo_transcripts_are_left_out() {
+        let root = std::env::temp_dir().join(format!("codelens-projects-{}", uuid::Uuid::new_v4()));
+        let transcript = |dir: &str, id: &str, cwd: Option<&str>| {
+            let cwd = cwd.map(|c| format!(r#""cwd":"{}","#, c)).unwrap_or_default();
+            let line = format!(
+                r#"{{"type":"user",{}"uuid":"{}-1","timestamp":"2026-03-02T10:00:00Z",
+                    "message":{{"role":"user","content":"Prompt in {}"}}}}"#,
+                cwd, id, id
+            )
+            .replace('\n', "");
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join(format!("{}.jsonl", id)), line).unwrap();
+        };
+        transcript("-work-app", "root", Some("/work/app"));
+        transcript("-work-app", "legacy", None);
+        transcript("-work-app-services-billing", "billing", Some("/work/app/services/billing"));
+        transcript("-work-app-v2", "sibling", Some("/work/app-v2"));
+        transcript("-work-app-v2", "sibling-legacy", None);
+        // `/work/app/v2` and `/work/app-v2` share an encoded directory
+        transcript("-work-app-v2", "nested", Some("/work/app/v2"));
+
+        let mut ids: Vec<String> = transcript_files_in(&root, "/work/app/")
+            .into_iter()
+            .map(|f| f.transcript_id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["billing", "legacy", "nested", "root"]);
+
+        let sibling: Vec<String> = transcript_files_in(&root, "/work/app-v2")
+            .into_iter()
+            .map(|f| f.transcript_id)
+            .collect();
+        assert_eq!(sibling.len(), 2);
+        assert!(sibling.iter().all(|id| id.starts_with("sibling")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn lines_without_ids_are_each_counted() {
+        let sessions = parse_fixture("unkeyed_usage", "s2");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].token_usage.input_tokens, 40);
+        assert_eq!(sessions[0].token_usage.output_tokens, 60);
+    }
 }
@@ -1,8 +1,369 @@
+use crate::claude::client::ClaudeClient;
+use crate::storage::secrets::{secret_store, API_KEY_SECRET};
+use crate::storage::settings::{load_settings, save_settings};
 use crate::types::AppSettings;
 
+/// Tauri command: the saved settings, without environment overrides. The API
+/// key is never included; see `get_api_key_status`.
 #[tauri::command]
-pub async fn update_settings(settings: AppSettings) -> Result<(), String> {
-    // TODO: Persist settings to disk
-    log::info!("Settings updated: model={}", settings.claude_model);
+pub async fn get_settings() -> Result<AppSettings, String> {
+    saved_settings()
+}
+
+/// Tauri command: validate and save the settings. Scans and enrichment pick
+/// them up on their next run. An `apiKey` is moved into the secret store.
+#[tauri::command]
+pub async fn update_settings(mut settings: AppSettings) -> Result<(), String> {
+    validate_settings(&settings)?;
+    if let Some(key) = settings.api_key.take().filter(|k| !k.is_empty()) {
+        secret_store()?.set(API_KEY_SECRET, &key)?;
+    }
+    save_settings(&settings)?;
+    log::info!(
+        "Settings updated: model={}, api={}",
+        settings.claude_model,
+        settings.api.base_url
+    );
     Ok(())
 }
+
+/// Check every value, reporting all problems at once.
+pub fn validate_settings(settings: &AppSettings) -> Result<(), String> {
+    let mut problems = scan_setting_problems(settings);
+    problems.extend(provider_setting_problems(settings));
+    problems.extend(api_setting_problems(settings));
+    into_result(problems)
+}
+
+/// Problems with the values scans use.
+fn scan_setting_problems(settings: &AppSettings) -> Vec<String> {
+    reset_invalid_scan_settings(&mut settings.clone())
+}
+
+/// Reset each invalid value scans use to its default, returning the problems.
+fn reset_invalid_scan_settings(settings: &mut AppSettings) -> Vec<String> {
+    let defaults = AppSettings::default();
+    let mut problems = Vec::new();
+    if !(1..=1024).contains(&settings.embedding_batch_size) {
+        problems.push("embeddingBatchSize must be between 1 and 1024".to_string());
+        settings.embedding_batch_size = defaults.embedding_batch_size;
+    }
+    if !(settings.clustering_eps > 0.0 && settings.clustering_eps <= 2.0) {
+        problems.push("clusteringEps must be above 0 and at most 2".to_string());
+        settings.clustering_eps = defaults.clustering_eps;
+    }
+    if settings.clustering_min_samples == 0 {
+        problems.push("clusteringMinSamples must be at least 1".to_string());
+        settings.clustering_min_samples = defaults.clustering_min_samples;
+    }
+    if settings.max_commits == 0 {
+        problems.push("maxCommits must be at least 1".to_string());
+        settings.max_commits = defaults.max_commits;
+    }
+    problems
+}
+
+/// Problems with the choice of provider and the OpenAI-compatible server.
+fn provider_setting_problems(settings: &AppSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+    match settings.provider.as_str() {
+        "anthropic" => {}
+        "openai" => {
+            if settings.openai_model.as_deref().is_none_or(|m| m.trim().is_empty()) {
+                problems.push("openaiModel must be set with the openai provider".to_string());
+            }
+        }
+        other => problems.push(format!(
+            "provider must be \"anthropic\" or \"openai\", got \"{}\"",
+            other
+        )),
+    }
+    let base_url = settings.openai_base_url.as_str();
+    if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+        problems.push(format!("openaiBaseUrl is not an HTTP(S) URL: {}", base_url));
+    }
+    problems
+}
+
+/// Problems with the values Claude API calls use.
+fn api_setting_problems(settings: &AppSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+    if settings.claude_model.trim().is_empty() {
+        problems.push("claudeModel must not be empty".to_string());
+    }
+    if !(1..=32).contains(&settings.max_concurrent_api_calls) {
+        problems.push("maxConcurrentApiCalls must be between 1 and 32".to_string());
+    }
+    if settings.api.request_timeout_secs == 0 || settings.api.connect_timeout_secs == 0 {
+        problems.push("API timeouts must be at least 1 second".to_string());
+    }
+    let base_url = settings.api.base_url.as_str();
+    if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+        problems.push(format!("api.baseUrl is not an HTTP(S) URL: {}", base_url));
+    }
+    // Headers or a proxy the HTTP client can't use
+    if let Err(e) = ClaudeClient::new(String::new(), settings.claude_model.clone(), &settings.api)
+    {
+        problems.push(e);
+    }
+    problems
+}
+
+fn into_result(problems: Vec<String>) -> Result<(), String> {
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Invalid settings: {}", problems.join("; ")))
+    }
+}
+
+/// The saved settings. An API key left in the file by an older version is
+/// moved into the secret store.
+fn saved_settings() -> Result<AppSettings, String> {
+    let settings = load_settings()?;
+    if let Some(key) = settings.api_key.as_deref().filter(|k| !k.is_empty()) {
+        let moved = secret_store()
+            .and_then(|store| store.set(API_KEY_SECRET, key).map(|()| store.kind()));
+        match moved {
+            Ok(kind) => {
+                // The key is not serialized, so this drops it from the file
+                save_settings(&settings)?;
+                log::info!("Moved the API key from the settings file to the {}", kind);
+            }
+            Err(e) => log::warn!("Failed to move the API key out of the settings file: {}", e),
+        }
+    }
+    Ok(settings)
+}
+
+/// The settings scans run with: the saved settings, overridden by environment
+/// variables. Scans never call the API, so its fields aren't checked, and a
+/// settings problem is logged and the defaults are used instead of failing.
+pub fn scan_settings() -> AppSettings {
+    scan_settings_from(load_settings(), |name| std::env::var(name).ok())
+}
+
+fn scan_settings_from(
+    saved: Result<AppSettings, String>,
+    var: impl Fn(&str) -> Option<String>,
+) -> AppSettings {
+    let mut settings = saved.unwrap_or_else(|e| {
+        log::warn!("{}; scanning with the default settings", e);
+        AppSettings::default()
+    });
+    if let Err(e) = apply_overrides(&mut settings, var) {
+        log::warn!("Ignoring environment override: {}", e);
+    }
+
+    let problems = reset_invalid_scan_settings(&mut settings);
+    if !problems.is_empty() {
+        log::warn!(
+            "Invalid settings: {}; scanning with their defaults",
+            problems.join("; ")
+        );
+    }
+    settings
+}
+
+/// The settings enrichment runs with: the saved settings, overridden by
+/// environment variables, with the API key from the secret store. Fails when
+/// a value the API calls use is invalid.
+pub fn resolved_settings() -> Result<AppSettings, String> {
+    let mut settings = saved_settings()?;
+    apply_overrides(&mut settings, |name| std::env::var(name).ok())?;
+    if settings.api_key.is_none() {
+        settings.api_key = secret_store()
+            .and_then(|store| store.get(API_KEY_SECRET))
+            .unwrap_or_else(|e| {
+                log::warn!("API key unavailable: {}", e);
+                None
+            });
+    }
+    let mut problems = provider_setting_problems(&settings);
+    problems.extend(api_setting_problems(&settings));
+    into_result(problems)?;
+    Ok(settings)
+}
+
+/// Override settings with the non-empty variables `var` returns:
+///
+/// - `ANTHROPIC_API_KEY`, `ANTHROPIC_BASE_URL`
+/// - `CLAUDE_MODEL`, `CLAUDE_MAX_CONCURRENT`
+/// - `LLM_PROVIDER`, `OPENAI_BASE_URL`, `OPENAI_MODEL`, `OPENAI_API_KEY`
+/// - `CODELENS_MAX_COMMITS`, `CODELENS_MODELS_DIR`
+///
+/// Variables that aren't numbers where one is expected are reported after the
+/// others have been applied.
+fn apply_overrides(
+    settings: &mut AppSettings,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    let var = |name: &str| var(name).filter(|v| !v.is_empty());
+    let mut problems = Vec::new();
+    let mut number = |name: &str| -> Option<u32> {
+        let value = var(name)?;
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            problems.push(format!("{} must be a positive number, got \"{}\"", name, value));
+        }
+        parsed
+    };
+
+    if let Some(key) = var("ANTHROPIC_API_KEY") {
+        settings.api_key = Some(key);
+    }
+    if let Some(url) = var("ANTHROPIC_BASE_URL") {
+        settings.api.base_url = url;
+    }
+    if let Some(model) = var("CLAUDE_MODEL") {
+        settings.claude_model = model;
+    }
+    if let Some(n) = number("CLAUDE_MAX_CONCURRENT") {
+        settings.max_concurrent_api_calls = n;
+    }
+    if let Some(provider) = var("LLM_PROVIDER") {
+        settings.provider = provider.trim().to_lowercase();
+    }
+    if let Some(url) = var("OPENAI_BASE_URL") {
+        settings.openai_base_url = url;
+    }
+    if let Some(model) = var("OPENAI_MODEL") {
+        settings.openai_model = Some(model);
+    }
+    if let Some(key) = var("OPENAI_API_KEY") {
+        settings.openai_api_key = Some(key);
+    }
+    if let Some(n) = number("CODELENS_MAX_COMMITS") {
+        settings.max_commits = n;
+    }
+    if let Some(dir) = var("CODELENS_MODELS_DIR") {
+        settings.models_dir = Some(dir);
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::settings::{load_settings_from, save_settings_to};
+    use std::collections::HashMap;
+
+    #[test]
+    fn saved_settings_are_validated_and_overridden_by_env() {
+        let dir = std::env::temp_dir().join(format!("codelens-settings-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("settings.json");
+        assert_eq!(load_settings_from(&path).unwrap().max_commits, 2000);
+
+        let mut settings = AppSettings {
+            clustering_eps: 0.45,
+            max_commits: 500,
+            ..AppSettings::default()
+        };
+        save_settings_to(&path, &settings).unwrap();
+        assert_eq!(load_settings_from(&path).unwrap().clustering_eps, 0.45);
+
+        // Fields missing from the file keep their default
+        std::fs::write(&path, r#"{"maxCommits": 500}"#).unwrap();
+        let loaded = load_settings_from(&path).unwrap();
+        assert_eq!(loaded.max_commits, 500);
+        assert_eq!(loaded.embedding_batch_size, 32);
+
+        // Keys of older settings files are read but never written back
+        std::fs::write(&path, r#"{"apiKey": "sk-ant-old"}"#).unwrap();
+        let loaded = load_settings_from(&path).unwrap();
+        assert_eq!(loaded.api_key.as_deref(), Some("sk-ant-old"));
+        save_settings_to(&path, &loaded).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("sk-ant"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let env = HashMap::from([
+            ("CLAUDE_MODEL", "claude-haiku-4-5"),
+            ("CODELENS_MAX_COMMITS", "100"),
+            ("CODELENS_MODELS_DIR", ""),
+        ]);
+        apply_overrides(&mut settings, |name| env.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(settings.claude_model, "claude-haiku-4-5");
+        assert_eq!(settings.max_commits, 100);
+        assert_eq!(settings.models_dir, None);
+        assert!(validate_settings(&settings).is_ok());
+
+        let local = HashMap::from([
+            ("LLM_PROVIDER", "OpenAI"),
+            ("OPENAI_BASE_URL", "http://localhost:11434/v1"),
+            ("OPENAI_MODEL", "qwen2.5-coder:14b"),
+            ("OPENAI_API_KEY", "local-key"),
+        ]);
+        let mut local_settings = settings.clone();
+        apply_overrides(&mut local_settings, |n| local.get(n).map(|v| v.to_string())).unwrap();
+        assert_eq!(local_settings.provider, "openai");
+        assert_eq!(local_settings.openai_base_url, "http://localhost:11434/v1");
+        assert_eq!(local_settings.openai_model.as_deref(), Some("qwen2.5-coder:14b"));
+        assert_eq!(local_settings.openai_api_key.as_deref(), Some("local-key"));
+        assert!(validate_settings(&local_settings).is_ok());
+
+        let bad = HashMap::from([("CLAUDE_MAX_CONCURRENT", "many")]);
+        let error = apply_overrides(&mut settings, |n| bad.get(n).map(|v| v.to_string()));
+        assert!(error.unwrap_err().contains("CLAUDE_MAX_CONCURRENT"));
+
+        settings.clustering_eps = 0.0;
+        settings.max_concurrent_api_calls = 0;
+        settings.api.base_url = "api.anthropic.com".to_string();
+        let error = validate_settings(&settings).unwrap_err();
+        assert!(error.contains("clusteringEps"));
+        assert!(error.contains("maxConcurrentApiCalls"));
+        assert!(error.contains("api.baseUrl"));
+    }
+
+    #[test]
+    fn the_openai_provider_needs_a_model() {
+        let mut settings = AppSettings {
+            provider: "openai".to_string(),
+            ..AppSettings::default()
+        };
+        let error = validate_settings(&settings).unwrap_err();
+        assert!(error.contains("openaiModel"));
+
+        settings.openai_model = Some("qwen2.5-coder:14b".to_string());
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.provider = "ollama".to_string();
+        settings.openai_base_url = "localhost:11434/v1".to_string();
+        let error = validate_settings(&settings).unwrap_err();
+        assert!(error.contains("provider must be"));
+        assert!(error.contains("openaiBaseUrl"));
+    }
+
+    #[test]
+    fn scans_fall_back_to_defaults_instead_of_failing() {
+        let env = HashMap::from([
+            ("CLAUDE_MAX_CONCURRENT", "many"),
+            ("CODELENS_MAX_COMMITS", "100"),
+        ]);
+        let var = |name: &str| env.get(name).map(|v| v.to_string());
+
+        let unreadable = Err("Invalid settings file settings.json: EOF".to_string());
+        let settings = scan_settings_from(unreadable, var);
+        assert_eq!(settings.max_commits, 100);
+        assert_eq!(settings.max_concurrent_api_calls, 3);
+
+        // Bad scan values fall back to their defaults; API values are left to enrichment
+        let saved = AppSettings {
+            clustering_eps: 0.0,
+            embedding_batch_size: 16,
+            api: crate::types::ApiSettings {
+                base_url: "api.anthropic.com".to_string(),
+                ..Default::default()
+            },
+            ..AppSettings::default()
+        };
+        let settings = scan_settings_from(Ok(saved), |_| None);
+        assert_eq!(settings.clustering_eps, 0.3);
+        assert_eq!(settings.embedding_batch_size, 16);
+        assert_eq!(settings.api.base_url, "api.anthropic.com");
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::types::{
+    CommitData, DateRange, FeatureCluster, ProjectData, PromptSession, RepositoryInfo,
+    Subproject, SubprojectSummary,
+};
+
+use super::cost::summarize_costs;
+use super::intent::{analyze_intents, summarize_intents};
+use super::scan::{compute_analytics, detect_languages};
+
+/// Check whether a repo-relative path falls under one of the subproject's prefixes.
+fn path_in_subproject(subproject: &Subproject, rel_path: &str) -> bool {
+    let rel_path = rel_path.trim_start_matches("./").trim_start_matches('/');
+    subproject.paths.iter().any(|prefix| {
+        let prefix = prefix.trim_matches('/');
+        prefix.is_empty()
+            || rel_path == prefix
+            || rel_path
+                .strip_prefix(prefix)
+                .map(|rest| rest.starts_with('/'))
+                .unwrap_or(false)
+    })
+}
+
+/// Make an absolute path (session cwd, tool call file path) relative to the repo root.
+/// Returns None when the path lives outside the repository.
+fn relative_to_repo<'a>(repo_path: &str, abs_path: &'a str) -> Option<&'a str> {
+    let root = repo_path.trim_end_matches('/');
+    if abs_path == root {
+        return Some("");
+    }
+    abs_path
+        .strip_prefix(root)
+        .filter(|rest| rest.starts_with('/'))
+        .map(|rest| rest.trim_start_matches('/'))
+}
+
+/// A session belongs to a subproject when Claude Code was started inside it.
+/// Sessions without a recorded cwd (or started at the repo root) fall back to
+/// the files they wrote.
+fn session_in_subproject(subproject: &Subproject, repo_path: &str, session: &PromptSession) -> bool {
+    if let Some(rel_cwd) = session
+        .cwd
+        .as_deref()
+        .and_then(|cwd| relative_to_repo(repo_path, cwd))
+    {
+        if !rel_cwd.is_empty() {
+            return path_in_subproject(subproject, rel_cwd);
+        }
+    }
+
+    !session.files_written.is_empty()
+        && session.files_written.iter().all(|f| {
+            relative_to_repo(repo_path, f)
+                .map(|rel| path_in_subproject(subproject, rel))
+                .unwrap_or(false)
+        })
+}
+
+/// A feature restricted to the commits in `commit_lookup`, with its line
+/// counts, change types and primary files recomputed from them. `None` when
+/// none of its commits are left.
+pub fn rescope_feature(
+    feature: &FeatureCluster,
+    commit_lookup: &HashMap<&str, &CommitData>,
+) -> Option<FeatureCluster> {
+    let scoped_commits: Vec<&CommitData> = feature
+        .commit_hashes
+        .iter()
+        .filter_map(|h| commit_lookup.get(h.as_str()).copied())
+        .collect();
+    if scoped_commits.is_empty() {
+        return None;
+    }
+
+    let mut scoped = feature.clone();
+    scoped.commit_hashes = scoped_commits.iter().map(|c| c.hash.clone()).collect();
+    scoped.total_lines_added = 0;
+    scoped.total_lines_removed = 0;
+    scoped.change_type_distribution = HashMap::new();
+
+    let mut file_set: HashMap<String, u32> = HashMap::new();
+    for commit in &scoped_commits {
+        *scoped
+            .change_type_distribution
+            .entry(commit.change_type.clone())
+            .or_insert(0) += 1;
+        for file in &commit.files_changed {
+            scoped.total_lines_added += file.lines_added;
+            scoped.total_lines_removed += file.lines_removed;
+            *file_set.entry(file.path.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let mut primary_files: Vec<String> = file_set.keys().cloned().collect();
+    primary_files.sort_by(|a, b| file_set[b].cmp(&file_set[a]));
+    primary_files.truncate(10);
+    scoped.primary_files = primary_files;
+    Some(scoped)
+}
+
+/// Build a `ProjectData` containing only the commits, features and prompts that
+/// belong to a subproject. Feature cluster ids are kept from the repo-level view
+/// so the scoped data rolls back up into it.
+pub fn scope_project_data(project: &ProjectData, subproject: &Subproject) -> ProjectData {
+    let repo_path = project.repository.path.as_str();
+
+    // Keep only the files under the subproject so per-file analytics stay scoped
+    let commits: Vec<CommitData> = project
+        .commits
+        .iter()
+        .filter_map(|commit| {
+            let files: Vec<_> = commit
+                .files_changed
+                .iter()
+                .filter(|f| path_in_subproject(subproject, &f.path))
+                .cloned()
+                .collect();
+            if files.is_empty() {
+                return None;
+            }
+            let mut scoped = commit.clone();
+            scoped.files_changed = files;
+            Some(scoped)
+        })
+        .collect();
+
+    let commit_lookup: HashMap<&str, &CommitData> =
+        commits.iter().map(|c| (c.hash.as_str(), c)).collect();
+
+    let mut prompt_sessions: Vec<PromptSession> = project
+        .prompt_sessions
+        .iter()
+        .filter(|s| session_in_subproject(subproject, repo_path, s))
+        .cloned()
+        .collect();
+    let prompt_keys: HashSet<(String, String)> = prompt_sessions
+        .iter()
+        .map(|s| (s.session_id.clone(), s.timestamp.clone()))
+        .collect();
+
+    let features: Vec<FeatureCluster> = project
+        .features
+        .iter()
+        .filter_map(|feature| {
+            let mut scoped = rescope_feature(feature, &commit_lookup)?;
+            scoped.sub_features.retain(|sub| {
+                prompt_keys.contains(&(sub.session_id.clone(), sub.timestamp.clone()))
+            });
+            scoped.cost_usd = scoped.sub_features.iter().map(|s| s.cost_usd).sum();
+
+            Some(scoped)
+        })
+        .collect();
+
+    let feature_ids: HashSet<i32> = features.iter().map(|f| f.cluster_id).collect();
+    for session in prompt_sessions.iter_mut() {
+        session
+            .associated_commit_hashes
+            .retain(|h| commit_lookup.contains_key(h.as_str()));
+        session
+            .associated_feature_ids
+            .retain(|id| feature_ids.contains(id));
+    }
+
+    let mut analytics = compute_analytics(&commits, &features);
+    analytics.total_prompts_detected = prompt_sessions.len() as u32;
+    summarize_costs(&mut analytics, &prompt_sessions, &features);
+    let intent_analyses = analyze_intents(&prompt_sessions, &commits);
+    summarize_intents(&mut analytics, &intent_analyses);
+    analytics.pricing_version = project.analytics.pricing_version.clone();
+
+    // Commits are kept in repo order (newest first)
+    let date_range = DateRange {
+        start: commits.last().map(|c| c.timestamp.clone()).unwrap_or_default(),
+        end: commits.first().map(|c| c.timestamp.clone()).unwrap_or_default(),
+    };
+
+    let repository = RepositoryInfo {
+        path: Path::new(repo_path)
+            .join(subproject.paths.first().map(|p| p.trim_matches('/')).unwrap_or(""))
+            .to_string_lossy()
+            .to_string(),
+        name: format!("{}/{}", project.repository.name, subproject.name),
+        total_commits: commits.len() as u32,
+        date_range,
+        languages_detected: detect_languages(&commits),
+    };
+
+    ProjectData {
+        id: None,
+        repository,
+        commits,
+        features,
+        prompt_sessions,
+        analytics,
+        subprojects: vec![],
+        // Redaction happens once at ingest, so the audit stays repo-wide
+        redaction: project.redaction.clone(),
+        intent_analyses,
+        project_config: project.project_config.clone(),
+        engine: project.engine.clone(),
+    }
+}
+
+/// Compute the per-subproject roll-ups attached to the repo-level view.
+pub fn build_subproject_summaries(
+    project: &ProjectData,
+    subprojects: &[Subproject],
+) -> Vec<SubprojectSummary> {
+    subprojects
+        .iter()
+        .map(|subproject| {
+            let scoped = scope_project_data(project, subproject);
+            SubprojectSummary {
+                name: subproject.name.clone(),
+                paths: subproject.paths.clone(),
+                commit_hashes: scoped.commits.iter().map(|c| c.hash.clone()).collect(),
+                feature_ids: scoped.features.iter().map(|f| f.cluster_id).collect(),
+                total_prompts: scoped.prompt_sessions.len() as u32,
+                analytics: scoped.analytics,
+            }
+        })
+        .collect()
+}
+
+/// Tauri command: narrow a scanned project down to one of its subprojects.
+#[tauri::command]
+pub async fn get_subproject_data(project: ProjectData, name: String) -> Result<ProjectData, String> {
+    let summary = project
+        .subprojects
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Subproject not found: {}", name))?;
+
+    let subproject = Subproject {
+        name: summary.name.clone(),
+        paths: summary.paths.clone(),
+    };
+
+    Ok(scope_project_data(&project, &subproject))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileChange, SubFeature};
+
+    const REPO: &str = "/home/dev/repo";
+
+    fn subproject(name: &str, paths: &[&str]) -> Subproject {
+        Subproject {
+            name: name.to_string(),
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn commit(hash: &str, change_type: &str, files: &[(&str, u32)]) -> CommitData {
+        CommitData {
+            hash: hash.to_string(),
+            change_type: change_type.to_string(),
+            files_changed: files
+                .iter()
+                .map(|(path, added)| FileChange {
+                    path: path.to_string(),
+                    lines_added: *added,
+                    lines_removed: 1,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn session(id: &str, cwd: Option<&str>, written: &[&str], cost_usd: f64) -> PromptSession {
+        PromptSession {
+            session_id: id.to_string(),
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            cwd: cwd.map(|c| format!("{}{}", REPO, c)),
+            files_written: written.iter().map(|f| format!("{}/{}", REPO, f)).collect(),
+            cost_usd,
+            ..Default::default()
+        }
+    }
+
+    fn sub_feature(session_id: &str, cost_usd: f64) -> SubFeature {
+        SubFeature {
+            session_id: session_id.to_string(),
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            cost_usd,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prefixes_match_whole_path_components() {
+        let web = subproject("web", &["web/"]);
+        assert!(path_in_subproject(&web, "web"));
+        assert!(path_in_subproject(&web, "web/src/app.ts"));
+        assert!(path_in_subproject(&web, "./web/index.html"));
+        assert!(!path_in_subproject(&web, "web-admin/src/app.ts"));
+        assert!(!path_in_subproject(&web, "api/web/handler.rs"));
+        assert!(path_in_subproject(&subproject("all", &["/"]), "anything.rs"));
+    }
+
+    #[test]
+    fn paths_are_made_relative_to_the_repo_root() {
+        assert_eq!(relative_to_repo(REPO, REPO), Some(""));
+        let nested = relative_to_repo("/home/dev/repo/", "/home/dev/repo/web/a.ts");
+        assert_eq!(nested, Some("web/a.ts"));
+        assert_eq!(relative_to_repo(REPO, "/home/dev/repo-old/web/a.ts"), None);
+        assert_eq!(relative_to_repo(REPO, "/tmp/scratch.txt"), None);
+    }
+
+    #[test]
+    fn sessions_belong_by_cwd_then_by_files_written() {
+        let web = subproject("web", &["web"]);
+        assert!(session_in_subproject(&web, REPO, &session("s", Some("/web/src"), &[], 0.0)));
+        // The cwd decides even when files elsewhere were written
+        let admin = session("s", Some("/web-admin"), &["web/a.ts"], 0.0);
+        assert!(!session_in_subproject(&web, REPO, &admin));
+
+        // Started at the repo root, so only the files written count
+        let root_web = session("s", Some(""), &["web/a.ts", "web/b.ts"], 0.0);
+        assert!(session_in_subproject(&web, REPO, &root_web));
+        let root_mixed = session("s", Some(""), &["web/a.ts", "api/main.rs"], 0.0);
+        assert!(!session_in_subproject(&web, REPO, &root_mixed));
+        let no_cwd = session("s", None, &["web/a.ts"], 0.0);
+        assert!(session_in_subproject(&web, REPO, &no_cwd));
+        assert!(!session_in_subproject(&web, REPO, &session("s", Some(""), &[], 0.0)));
+    }
+
+    #[test]
+    fn features_are_rescoped_to_the_remaining_commits() {
+        let feature = FeatureCluster {
+            cluster_id: 4,
+            commit_hashes: vec!["a".to_string(), "b".to_string()],
+            total_lines_added: 100,
+            total_lines_removed: 50,
+            ..Default::default()
+        };
+        let b = commit("b", "bugfix", &[("web/a.ts", 3), ("web/b.ts", 4)]);
+        let lookup: HashMap<&str, &CommitData> = [("b", &b)].into_iter().collect();
+
+        let scoped = rescope_feature(&feature, &lookup).unwrap();
+        assert_eq!(scoped.cluster_id, 4);
+        assert_eq!(scoped.commit_hashes, ["b"]);
+        assert_eq!((scoped.total_lines_added, scoped.total_lines_removed), (7, 2));
+        assert_eq!(scoped.change_type_distribution, HashMap::from([("bugfix".to_string(), 1)]));
+        assert_eq!(scoped.primary_files.len(), 2);
+
+        assert!(rescope_feature(&feature, &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn subprojects_roll_up_to_the_repo_totals() {
+        let commits = vec![
+            commit("c3", "feature", &[("api/main.rs", 8)]),
+            commit("c2", "bugfix", &[("web/a.ts", 5), ("api/lib.rs", 2)]),
+            commit("c1", "feature", &[("web/b.ts", 10)]),
+        ];
+        let features = vec![FeatureCluster {
+            cluster_id: 0,
+            commit_hashes: commits.iter().map(|c| c.hash.clone()).collect(),
+            sub_features: vec![sub_feature("web-session", 1.5), sub_feature("api-session", 0.5)],
+            cost_usd: 2.0,
+            ..Default::default()
+        }];
+        let mut project = ProjectData {
+            commits,
+            features,
+            prompt_sessions: vec![
+                session("web-session", Some("/web"), &[], 1.5),
+                session("api-session", Some(""), &["api/main.rs"], 0.5),
+            ],
+            ..Default::default()
+        };
+        project.repository.path = REPO.to_string();
+        project.repository.name = "repo".to_string();
+        project.analytics = compute_analytics(&project.commits, &project.features);
+
+        let parts = [subproject("web", &["web"]), subproject("api", &["api"])];
+        let summaries = build_subproject_summaries(&project, &parts);
+        assert_eq!(summaries[0].commit_hashes, ["c2", "c1"]);
+        assert_eq!(summaries[1].commit_hashes, ["c3", "c2"]);
+
+        let scoped: Vec<ProjectData> =
+            parts.iter().map(|p| scope_project_data(&project, p)).collect();
+        assert_eq!(scoped[0].repository.name, "repo/web");
+        assert_eq!(scoped[0].repository.path, format!("{}/web", REPO));
+
+        // The feature keeps its id in both, and its lines and cost split between them
+        let lines: u32 = scoped.iter().map(|s| s.features[0].total_lines_added).sum();
+        assert_eq!(lines, 25);
+        assert!(scoped.iter().all(|s| s.features[0].cluster_id == 0));
+        let cost: f64 = scoped.iter().map(|s| s.analytics.total_cost_usd).sum();
+        assert_eq!(cost, 2.0);
+        let prompts: u32 = summaries.iter().map(|s| s.total_prompts).sum();
+        assert_eq!(prompts, 2);
+
+        // c2 touches both, so its change type is counted in each
+        assert_eq!(scoped[0].analytics.change_type_totals["bugfix"], 1);
+        assert_eq!(scoped[1].analytics.change_type_totals["bugfix"], 1);
+        assert_eq!(project.analytics.change_type_totals["bugfix"], 1);
+    }
+}
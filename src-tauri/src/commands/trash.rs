@@ -0,0 +1,441 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::storage::db::{app_data_dir, Database};
+use crate::types::{
+    ArchivedFile, DeleteSessionsResult, PromptSession, RestoreSessionsResult,
+    SessionArchive, SessionFileInfo, SessionFilter,
+};
+
+use super::redact::project_redactor;
+use super::sessions::{transcript_files_for_repo, TranscriptFile};
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Trash directory: `<data_dir>/codelens/trash/<archive_id>/`.
+fn trash_dir() -> Result<PathBuf, String> {
+    let dir = app_data_dir()?.join("trash");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create trash directory: {}", e))?;
+    Ok(dir)
+}
+
+fn archive_dir(archive_id: &str) -> Result<PathBuf, String> {
+    if archive_id.is_empty() || archive_id.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid archive id: {}", archive_id));
+    }
+    Ok(trash_dir()?.join(archive_id))
+}
+
+/// Parse a filter bound: an RFC 3339 timestamp, or a `YYYY-MM-DD` date taken as
+/// the start (or end) of that day in UTC.
+fn parse_bound(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date (expected RFC 3339 or YYYY-MM-DD): {}", value))?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    };
+    Ok(time.expect("valid time of day").and_utc())
+}
+
+/// A transcript matches when it belongs to one of the requested sessions and its
+/// whole activity lies within the date range.
+fn matches_filter(
+    file: &TranscriptFile,
+    filter: &SessionFilter,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> bool {
+    if !filter.session_ids.is_empty()
+        && !filter
+            .session_ids
+            .iter()
+            .any(|id| *id == file.session_id || *id == file.transcript_id)
+    {
+        return false;
+    }
+
+    let parse = |ts: &str| DateTime::parse_from_rfc3339(ts).ok().map(|d| d.with_timezone(&Utc));
+    let first = parse(&file.first_timestamp);
+    let last = parse(&file.last_timestamp);
+    since.is_none_or(|since| first.is_some_and(|first| first >= since))
+        && until.is_none_or(|until| last.is_some_and(|last| last <= until))
+}
+
+/// The JSONL file plus every file in its companion session directory, if any.
+fn files_of(transcript: &TranscriptFile) -> Vec<PathBuf> {
+    let mut files = vec![transcript.path.clone()];
+    let mut pending = vec![transcript.path.with_extension("")];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn file_info(transcript: &TranscriptFile) -> SessionFileInfo {
+    SessionFileInfo {
+        path: transcript.path.to_string_lossy().to_string(),
+        session_id: transcript.session_id.clone(),
+        transcript_id: transcript.transcript_id.clone(),
+        size_bytes: files_of(transcript).iter().map(|f| file_size(f)).sum(),
+        first_timestamp: transcript.first_timestamp.clone(),
+        last_timestamp: transcript.last_timestamp.clone(),
+        prompt_count: transcript.sessions.len() as u32,
+    }
+}
+
+/// Gzip every file of the selected transcripts into a new archive directory and
+/// write its manifest. The originals are left untouched.
+fn write_archive(
+    archive_id: &str,
+    repo_path: &str,
+    transcripts: &[TranscriptFile],
+) -> Result<SessionArchive, String> {
+    let dir = archive_dir(archive_id)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create archive: {}", e))?;
+
+    let mut files: Vec<ArchivedFile> = Vec::new();
+    for (i, path) in transcripts.iter().flat_map(files_of).enumerate() {
+        let stored_as = format!("{:05}.gz", i);
+        let mut input = BufReader::new(
+            File::open(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+        );
+        let output = File::create(dir.join(&stored_as))
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(output), Compression::default());
+        io::copy(&mut input, &mut encoder)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| format!("Failed to compress {}: {}", path.display(), e))?;
+
+        files.push(ArchivedFile {
+            original_path: path.to_string_lossy().to_string(),
+            stored_as,
+            size_bytes: file_size(&path),
+        });
+    }
+
+    let compressed_bytes = files
+        .iter()
+        .map(|f| file_size(&dir.join(&f.stored_as)))
+        .sum();
+    let archive = SessionArchive {
+        id: archive_id.to_string(),
+        repo_path: repo_path.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        total_bytes: files.iter().map(|f| f.size_bytes).sum(),
+        compressed_bytes,
+        files,
+    };
+
+    write_manifest(&dir, &archive)?;
+    Ok(archive)
+}
+
+fn write_manifest(dir: &Path, archive: &SessionArchive) -> Result<(), String> {
+    let manifest = serde_json::to_string_pretty(archive)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(dir.join(MANIFEST_FILE), manifest)
+        .map_err(|e| format!("Failed to write manifest: {}", e))
+}
+
+fn read_manifest(dir: &Path) -> Result<SessionArchive, String> {
+    let content = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Failed to read archive manifest: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid archive manifest: {}", e))
+}
+
+/// Tauri command: move a repo's Claude Code session files to the CodeLens trash.
+///
+/// With `dry_run` the matching files and their sizes are only listed. Otherwise
+/// the parsed prompts are snapshotted into storage first (so they stay in CodeLens
+/// history), the files are gzipped into a trash archive, and only then removed.
+#[tauri::command]
+pub async fn delete_sessions(
+    path: String,
+    filter: Option<SessionFilter>,
+    dry_run: Option<bool>,
+) -> Result<DeleteSessionsResult, String> {
+    let filter = filter.unwrap_or_default();
+    let dry_run = dry_run.unwrap_or(false);
+    let since = filter.since.as_deref().map(|s| parse_bound(s, false)).transpose()?;
+    let until = filter.until.as_deref().map(|s| parse_bound(s, true)).transpose()?;
+
+    let selected: Vec<TranscriptFile> = transcript_files_for_repo(&path)
+        .into_iter()
+        .filter(|f| matches_filter(f, &filter, since, until))
+        .collect();
+    let files: Vec<SessionFileInfo> = selected.iter().map(file_info).collect();
+    let total_bytes = files.iter().map(|f| f.size_bytes).sum();
+
+    if dry_run || selected.is_empty() {
+        return Ok(DeleteSessionsResult {
+            dry_run,
+            files,
+            total_bytes,
+            archive_id: None,
+        });
+    }
+
+    let redactor = project_redactor(Path::new(&path))?;
+    let mut sessions: Vec<PromptSession> =
+        selected.iter().flat_map(|f| f.sessions.clone()).collect();
+    redactor.redact_sessions(&mut sessions);
+
+    let archive_id = uuid::Uuid::new_v4().to_string();
+    let db = Database::open_default()?;
+    db.save_session_snapshot(&archive_id, &path, &sessions)?;
+
+    let archive = match write_archive(&archive_id, &path, &selected) {
+        Ok(a) => a,
+        Err(e) => {
+            let _ = db.delete_session_snapshot(&archive_id);
+            if let Ok(dir) = archive_dir(&archive_id) {
+                let _ = fs::remove_dir_all(dir);
+            }
+            return Err(e);
+        }
+    };
+
+    for transcript in &selected {
+        if let Err(e) = fs::remove_file(&transcript.path) {
+            log::warn!("Failed to remove {}: {}", transcript.path.display(), e);
+        }
+        let session_dir = transcript.path.with_extension("");
+        if session_dir.is_dir() {
+            let _ = fs::remove_dir_all(&session_dir);
+        }
+    }
+
+    log::info!(
+        "Moved {} session files ({} bytes, {} compressed) to trash archive {}",
+        archive.files.len(),
+        archive.total_bytes,
+        archive.compressed_bytes,
+        archive_id
+    );
+
+    Ok(DeleteSessionsResult {
+        dry_run,
+        files,
+        total_bytes,
+        archive_id: Some(archive_id),
+    })
+}
+
+/// Files of an archive sorted by what happened to them on restore.
+struct RestoreOutcome {
+    restored: Vec<ArchivedFile>,
+    /// Files whose original path exists again
+    skipped: Vec<ArchivedFile>,
+    /// Files left alone after a failure, including the one that failed
+    failed: Vec<ArchivedFile>,
+    error: Option<String>,
+}
+
+fn restore_file(dir: &Path, file: &ArchivedFile) -> Result<(), String> {
+    let target = Path::new(&file.original_path);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let input = File::open(dir.join(&file.stored_as))
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    let mut output = File::create(target)
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    if let Err(e) = io::copy(&mut GzDecoder::new(BufReader::new(input)), &mut output) {
+        // A partial file would be skipped as existing by the next restore
+        let _ = fs::remove_file(target);
+        return Err(format!("Failed to restore {}: {}", target.display(), e));
+    }
+    Ok(())
+}
+
+/// Put the archived files back where they were, stopping at the first failure.
+fn restore_files(dir: &Path, files: &[ArchivedFile]) -> RestoreOutcome {
+    let mut outcome = RestoreOutcome {
+        restored: Vec::new(),
+        skipped: Vec::new(),
+        failed: Vec::new(),
+        error: None,
+    };
+    for file in files {
+        if outcome.error.is_some() {
+            outcome.failed.push(file.clone());
+        } else if Path::new(&file.original_path).exists() {
+            log::warn!("Not restoring {}: file already exists", file.original_path);
+            outcome.skipped.push(file.clone());
+        } else {
+            match restore_file(dir, file) {
+                Ok(()) => outcome.restored.push(file.clone()),
+                Err(e) => {
+                    outcome.error = Some(e);
+                    outcome.failed.push(file.clone());
+                }
+            }
+        }
+    }
+    outcome
+}
+
+/// Shrink an archive to the files in `kept`: the manifest is rewritten first,
+/// then the other compressed files are removed.
+fn prune_archive(
+    dir: &Path,
+    archive: SessionArchive,
+    kept: Vec<ArchivedFile>,
+) -> Result<(), String> {
+    let removed: Vec<ArchivedFile> = archive
+        .files
+        .into_iter()
+        .filter(|f| !kept.iter().any(|k| k.stored_as == f.stored_as))
+        .collect();
+    let pruned = SessionArchive {
+        total_bytes: kept.iter().map(|f| f.size_bytes).sum(),
+        compressed_bytes: kept.iter().map(|f| file_size(&dir.join(&f.stored_as))).sum(),
+        files: kept,
+        ..archive
+    };
+    write_manifest(dir, &pruned)?;
+
+    for file in removed {
+        let _ = fs::remove_file(dir.join(&file.stored_as));
+    }
+    Ok(())
+}
+
+/// Tauri command: put the files of a trash archive back where they were.
+///
+/// Files that exist again at their original path are left alone and reported as
+/// skipped. The archive and its snapshot are only dropped once every file is
+/// back; otherwise the archive keeps just the files that weren't restored.
+#[tauri::command]
+pub async fn restore_sessions(archive_id: String) -> Result<RestoreSessionsResult, String> {
+    let dir = archive_dir(&archive_id)?;
+    let archive = read_manifest(&dir)?;
+    let outcome = restore_files(&dir, &archive.files);
+
+    let skipped: Vec<String> = outcome
+        .skipped
+        .iter()
+        .map(|f| f.original_path.clone())
+        .collect();
+    let kept: Vec<ArchivedFile> = outcome.skipped.into_iter().chain(outcome.failed).collect();
+    if kept.is_empty() {
+        // The transcripts are live again, so the snapshot and archive are no longer needed
+        Database::open_default()?.delete_session_snapshot(&archive_id)?;
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove archive: {}", e))?;
+    } else if !outcome.restored.is_empty() {
+        // The snapshot stays: it still holds the prompts of the files left archived
+        prune_archive(&dir, archive, kept)?;
+    }
+
+    if let Some(e) = outcome.error {
+        return Err(e);
+    }
+    Ok(RestoreSessionsResult {
+        restored: outcome.restored.len() as u32,
+        skipped,
+    })
+}
+
+/// Tauri command: list the trash archives of a repo, newest first.
+#[tauri::command]
+pub async fn list_session_archives(path: String) -> Result<Vec<SessionArchive>, String> {
+    let entries = match fs::read_dir(trash_dir()?) {
+        Ok(e) => e,
+        Err(_) => return Ok(vec![]),
+    };
+
+    let mut archives: Vec<SessionArchive> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| read_manifest(&entry.path()).ok())
+        .filter(|archive| archive.repo_path == path)
+        .collect();
+    archives.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    Ok(archives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn skipped_files_stay_in_the_archive() {
+        let root = std::env::temp_dir().join(format!("codelens-trash-{}", uuid::Uuid::new_v4()));
+        let dir = root.join("archive");
+        fs::create_dir_all(&dir).unwrap();
+        let archived = |name: &str, stored_as: &str, content: &str| {
+            let mut encoder = GzEncoder::new(
+                File::create(dir.join(stored_as)).unwrap(),
+                Compression::default(),
+            );
+            encoder.write_all(content.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+            ArchivedFile {
+                original_path: root.join("project").join(name).to_string_lossy().to_string(),
+                stored_as: stored_as.to_string(),
+                size_bytes: content.len() as u64,
+            }
+        };
+        let archive = SessionArchive {
+            id: "archive".to_string(),
+            repo_path: "/work/app".to_string(),
+            created_at: "2026-03-02T10:00:00Z".to_string(),
+            files: vec![
+                archived("gone.jsonl", "00000.gz", "archived gone"),
+                archived("back.jsonl", "00001.gz", "archived back"),
+            ],
+            total_bytes: 26,
+            compressed_bytes: 0,
+        };
+        // A newer transcript was written to the second path since the delete
+        let back = root.join("project").join("back.jsonl");
+        fs::create_dir_all(back.parent().unwrap()).unwrap();
+        fs::write(&back, "live").unwrap();
+
+        let outcome = restore_files(&dir, &archive.files);
+        assert!(outcome.error.is_none());
+        assert_eq!(outcome.restored.len(), 1);
+        assert_eq!(outcome.skipped[0].stored_as, "00001.gz");
+        let gone = root.join("project").join("gone.jsonl");
+        assert_eq!(fs::read_to_string(gone).unwrap(), "archived gone");
+        assert_eq!(fs::read_to_string(&back).unwrap(), "live");
+
+        prune_archive(&dir, archive, outcome.skipped).unwrap();
+        let pruned = read_manifest(&dir).unwrap();
+        assert_eq!(pruned.files.len(), 1);
+        assert_eq!(pruned.files[0].original_path, back.to_string_lossy());
+        assert_eq!(pruned.total_bytes, 13);
+        assert!(!dir.join("00000.gz").exists());
+        assert!(dir.join("00001.gz").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,12 +1,17 @@
 mod commands;
 mod claude;
+mod llm;
 mod mojo_bridge;
 mod storage;
 mod types;
 
 use commands::{
-    delete_sessions, enrich_features, export_report, get_feature_detail, get_function_history,
-    get_project_data, get_sessions, list_projects, scan_repository, search, update_settings,
+    clear_api_key, delete_sessions, enrich_features, export_report, export_sessions,
+    extract_prompt_intents, get_api_key_status, get_developer_profile, get_feature_detail,
+    get_function_history, get_project_data, get_sessions, get_settings, get_subproject_data,
+    import_sessions, list_enrichment_batches, list_projects, list_session_archives,
+    poll_enrichment_batches, restore_sessions, resume_enrichment_batches, scan_repository, search,
+    set_api_key, stream_feature_narrative, submit_enrichment_batch, test_api_key, update_settings,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -19,17 +24,38 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            // Follow up enrichment batches submitted before the last shutdown
+            tauri::async_runtime::spawn(resume_enrichment_batches(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             scan_repository,
             enrich_features,
+            stream_feature_narrative,
+            submit_enrichment_batch,
+            list_enrichment_batches,
+            poll_enrichment_batches,
+            extract_prompt_intents,
             get_project_data,
             get_sessions,
+            get_subproject_data,
+            get_developer_profile,
             delete_sessions,
+            restore_sessions,
+            list_session_archives,
+            export_sessions,
+            import_sessions,
             list_projects,
             get_feature_detail,
             search,
             get_function_history,
+            get_settings,
             update_settings,
+            get_api_key_status,
+            set_api_key,
+            clear_api_key,
+            test_api_key,
             export_report,
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+
+use super::LlmProvider;
+use crate::claude::client::ClaudeClient;
+use crate::claude::error::ClaudeError;
+use crate::claude::types::{SystemBlock, Tool};
+use crate::types::TokenUsage;
+
+#[async_trait]
+impl LlmProvider for ClaudeClient {
+    fn model(&self) -> &str {
+        ClaudeClient::model(self)
+    }
+
+    fn usage(&self) -> TokenUsage {
+        ClaudeClient::usage(self)
+    }
+
+    async fn call_tool(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        tool: Tool,
+    ) -> Result<serde_json::Value, ClaudeError> {
+        ClaudeClient::call_tool(self, system, user_message, max_tokens, tool).await
+    }
+
+    async fn stream_message(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        on_text: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String, ClaudeError> {
+        ClaudeClient::stream_message(self, system, user_message, max_tokens, on_text).await
+    }
+}
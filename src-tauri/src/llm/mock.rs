@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::LlmProvider;
+use crate::claude::error::ClaudeError;
+use crate::claude::types::{SystemBlock, Tool};
+use crate::types::TokenUsage;
+
+/// Deterministic provider for tests. Tool calls are answered with the input
+/// registered for the tool, and streams send the canned text word by word.
+/// Messages containing a registered failure marker fail with a server error.
+/// Usage counts one token per four characters.
+#[derive(Default)]
+pub struct MockProvider {
+    tool_inputs: HashMap<String, Value>,
+    text: String,
+    failures: Vec<String>,
+    messages: Mutex<Vec<String>>,
+    usage: Mutex<TokenUsage>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer calls to `tool_name` with `input`.
+    pub fn with_tool_input(mut self, tool_name: &str, input: Value) -> Self {
+        self.tool_inputs.insert(tool_name.to_string(), input);
+        self
+    }
+
+    /// Stream `text` in reply to every message.
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    /// Fail every message that contains `marker`.
+    pub fn failing_on(mut self, marker: &str) -> Self {
+        self.failures.push(marker.to_string());
+        self
+    }
+
+    /// User messages received so far, in order.
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().expect("messages lock poisoned").clone()
+    }
+
+    /// Record a message and its usage, or fail it.
+    fn receive(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        reply: &str,
+    ) -> Result<(), ClaudeError> {
+        self.messages
+            .lock()
+            .expect("messages lock poisoned")
+            .push(user_message.to_string());
+        if let Some(marker) = self.failures.iter().find(|m| user_message.contains(m.as_str())) {
+            return Err(ClaudeError::Server {
+                status: 500,
+                message: format!("mock failure for {}", marker),
+            });
+        }
+
+        let input_chars: usize =
+            system.iter().map(|b| b.text.len()).sum::<usize>() + user_message.len();
+        let mut usage = self.usage.lock().expect("usage lock poisoned");
+        usage.input_tokens += (input_chars / 4) as u64;
+        usage.output_tokens += (reply.len() / 4) as u64;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn model(&self) -> &str {
+        "mock-model"
+    }
+
+    fn usage(&self) -> TokenUsage {
+        self.usage.lock().expect("usage lock poisoned").clone()
+    }
+
+    async fn call_tool(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        _max_tokens: u32,
+        tool: Tool,
+    ) -> Result<Value, ClaudeError> {
+        let input = self
+            .tool_inputs
+            .get(&tool.name)
+            .cloned()
+            .ok_or(ClaudeError::EmptyResponse)?;
+        self.receive(system, user_message, &input.to_string())?;
+        Ok(input)
+    }
+
+    async fn stream_message(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        _max_tokens: u32,
+        on_text: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String, ClaudeError> {
+        self.receive(system, user_message, &self.text)?;
+        if self.text.is_empty() {
+            return Err(ClaudeError::EmptyResponse);
+        }
+        for piece in self.text.split_inclusive(' ') {
+            on_text(piece);
+        }
+        Ok(self.text.clone())
+    }
+}
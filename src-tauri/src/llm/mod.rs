@@ -0,0 +1,42 @@
+pub mod anthropic;
+#[cfg(test)]
+pub mod mock;
+pub mod openai;
+
+use async_trait::async_trait;
+
+use crate::claude::error::ClaudeError;
+use crate::claude::types::{SystemBlock, Tool};
+use crate::types::TokenUsage;
+
+/// A chat model that answers the single-turn requests of the enrichment
+/// pipeline. Requests are expressed in Messages API terms; other backends
+/// translate them, and report failures as the matching `ClaudeError`.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Model name, part of every cache key.
+    fn model(&self) -> &str;
+
+    /// Token usage of every request completed so far.
+    fn usage(&self) -> TokenUsage;
+
+    /// Send a message that must be answered by calling `tool`, and return the
+    /// tool input the model produced.
+    async fn call_tool(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        tool: Tool,
+    ) -> Result<serde_json::Value, ClaudeError>;
+
+    /// Stream a reply, passing each piece of text to `on_text` as it arrives,
+    /// and return the full text.
+    async fn stream_message(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        on_text: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String, ClaudeError>;
+}
@@ -0,0 +1,398 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::LlmProvider;
+use crate::claude::error::ClaudeError;
+use crate::claude::retry::{RetryHint, RetryPolicy};
+use crate::claude::stream::SseDecoder;
+use crate::claude::types::{SystemBlock, Tool};
+use crate::types::{ApiSettings, TokenUsage};
+
+/// A server implementing OpenAI's Chat Completions API, such as llama.cpp's
+/// `llama-server`, Ollama, vLLM or LM Studio.
+///
+/// The system blocks are joined into one system message; prompt caching is
+/// left to the server. Forced tool calls become `tool_choice` functions.
+pub struct OpenAiCompatibleProvider {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    retry: RetryPolicy,
+    usage: Mutex<TokenUsage>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    max_tokens: u32,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    /// Ask for a final chunk with the usage, which streams otherwise omit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatReply,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatReply {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    function: FunctionCall,
+}
+
+/// `arguments` is a JSON string per the spec, but some servers send an object.
+#[derive(Debug, Deserialize)]
+struct FunctionCall {
+    name: String,
+    arguments: Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+/// One `data` payload of a streamed completion.
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    usage: Option<ChatUsage>,
+    error: Option<ChunkError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    delta: ChunkDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkDelta {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkError {
+    message: String,
+}
+
+impl OpenAiCompatibleProvider {
+    /// `base_url` is the API root, e.g. `http://localhost:8080/v1`. Local
+    /// servers usually need no `api_key`. Only the timeouts of `settings` apply;
+    /// its base URL, headers and proxy are for the Anthropic API.
+    pub fn new(
+        base_url: &str,
+        api_key: Option<String>,
+        model: String,
+        settings: &ApiSettings,
+    ) -> Result<Self, String> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(settings.request_timeout_secs))
+            .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+            retry: RetryPolicy::from_env(),
+            usage: Mutex::new(TokenUsage::default()),
+        })
+    }
+
+    fn request(&self, system: &[SystemBlock], user_message: &str, max_tokens: u32) -> ChatRequest {
+        let system: Vec<&str> = system.iter().map(|b| b.text.as_str()).collect();
+        ChatRequest {
+            model: self.model.clone(),
+            max_tokens,
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: system.join("\n\n"),
+                },
+                ChatMessage {
+                    role: "user",
+                    content: user_message.to_string(),
+                },
+            ],
+            tools: vec![],
+            tool_choice: None,
+            stream: false,
+            stream_options: None,
+        }
+    }
+
+    fn record_usage(&self, usage: &ChatUsage) {
+        let mut total = self.usage.lock().expect("usage lock poisoned");
+        total.input_tokens += u64::from(usage.prompt_tokens);
+        total.output_tokens += u64::from(usage.completion_tokens);
+    }
+
+    /// Post a request, retrying like `ClaudeClient` does, and return the
+    /// response once its status is a success.
+    async fn post(&self, request: &ChatRequest) -> Result<Response, ClaudeError> {
+        self.retry.run(|| self.try_post(request)).await
+    }
+
+    /// One attempt at `post`. On failure, also returns the server's retry guidance.
+    async fn try_post(&self, request: &ChatRequest) -> Result<Response, (ClaudeError, RetryHint)> {
+        let mut builder = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(request);
+        if let Some(key) = &self.api_key {
+            builder = builder.bearer_auth(key);
+        }
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| (ClaudeError::from(e), RetryHint::default()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let hint = RetryHint::from_headers(response.headers());
+            let body = response.text().await.unwrap_or_default();
+            return Err((ClaudeError::from_status(status.as_u16(), &body, hint.delay), hint));
+        }
+        Ok(response)
+    }
+}
+
+/// The tool input in a reply to a forced call of `tool_name`. Models without
+/// tool support often answer with the JSON object as plain text instead,
+/// which is accepted too.
+fn tool_input(response: ChatResponse, tool_name: &str) -> Result<Value, ClaudeError> {
+    let choice = response
+        .choices
+        .into_iter()
+        .next()
+        .ok_or(ClaudeError::EmptyResponse)?;
+    if choice.finish_reason.as_deref() == Some("length") {
+        return Err(ClaudeError::Parse(format!(
+            "Tool call to {} was cut off at max_tokens",
+            tool_name
+        )));
+    }
+
+    let arguments = match choice
+        .message
+        .tool_calls
+        .into_iter()
+        .find(|call| call.function.name == tool_name)
+    {
+        Some(call) => call.function.arguments,
+        None => Value::String(choice.message.content.ok_or(ClaudeError::EmptyResponse)?),
+    };
+    match arguments {
+        Value::String(text) => {
+            let text = text.trim();
+            let text = text
+                .strip_prefix("```json")
+                .or_else(|| text.strip_prefix("```"))
+                .and_then(|t| t.strip_suffix("```"))
+                .unwrap_or(text);
+            serde_json::from_str(text).map_err(|e| {
+                ClaudeError::Parse(format!("Invalid {} arguments: {}", tool_name, e))
+            })
+        }
+        object @ Value::Object(_) => Ok(object),
+        _ => Err(ClaudeError::EmptyResponse),
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn usage(&self) -> TokenUsage {
+        self.usage.lock().expect("usage lock poisoned").clone()
+    }
+
+    async fn call_tool(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        tool: Tool,
+    ) -> Result<Value, ClaudeError> {
+        let mut request = self.request(system, user_message, max_tokens);
+        request.tool_choice = Some(json!({
+            "type": "function",
+            "function": { "name": tool.name },
+        }));
+        request.tools = vec![json!({
+            "type": "function",
+            "function": {
+                "name": tool.name,
+                "description": tool.description,
+                "parameters": tool.input_schema,
+            },
+        })];
+
+        let response: ChatResponse = self
+            .post(&request)
+            .await?
+            .json()
+            .await
+            .map_err(|e| ClaudeError::Parse(e.to_string()))?;
+        if let Some(usage) = &response.usage {
+            self.record_usage(usage);
+        }
+        tool_input(response, &tool.name)
+    }
+
+    async fn stream_message(
+        &self,
+        system: &[SystemBlock],
+        user_message: &str,
+        max_tokens: u32,
+        on_text: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String, ClaudeError> {
+        let mut request = self.request(system, user_message, max_tokens);
+        request.stream = true;
+        request.stream_options = Some(json!({ "include_usage": true }));
+        let mut response = self.post(&request).await?;
+
+        let mut decoder = SseDecoder::default();
+        let mut text = String::new();
+        let mut finished = false;
+        while let Some(chunk) = response.chunk().await.map_err(ClaudeError::from)? {
+            for data in decoder.push_data(&chunk) {
+                if data == "[DONE]" {
+                    return if text.is_empty() {
+                        Err(ClaudeError::EmptyResponse)
+                    } else {
+                        Ok(text)
+                    };
+                }
+                let chunk: ChatChunk = serde_json::from_str(&data)
+                    .map_err(|e| ClaudeError::Parse(format!("Invalid stream chunk: {}", e)))?;
+                if let Some(error) = chunk.error {
+                    return Err(ClaudeError::Server {
+                        status: 500,
+                        message: error.message,
+                    });
+                }
+                if let Some(usage) = &chunk.usage {
+                    self.record_usage(usage);
+                }
+                for choice in chunk.choices {
+                    if let Some(delta) = choice.delta.content.filter(|d| !d.is_empty()) {
+                        on_text(&delta);
+                        text.push_str(&delta);
+                    }
+                    if let Some(reason) = choice.finish_reason {
+                        if reason == "length" {
+                            log::warn!("Streamed reply was cut off at max_tokens");
+                        }
+                        finished = true;
+                    }
+                }
+            }
+        }
+
+        // Some servers close the stream without a final `[DONE]`
+        if finished && !text.is_empty() {
+            Ok(text)
+        } else {
+            Err(ClaudeError::Network(
+                "stream ended before the reply finished".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tool_calls_and_plain_json_replies() {
+        let reply = |message: Value, finish_reason: &str| -> ChatResponse {
+            serde_json::from_value(json!({
+                "choices": [{ "message": message, "finish_reason": finish_reason }],
+                "usage": { "prompt_tokens": 10, "completion_tokens": 5 }
+            }))
+            .unwrap()
+        };
+
+        let called = reply(
+            json!({
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": { "name": "record", "arguments": "{\"title\":\"Export\"}" }
+                }]
+            }),
+            "tool_calls",
+        );
+        assert_eq!(tool_input(called, "record").unwrap()["title"], "Export");
+
+        let fenced = reply(json!({ "content": "```json\n{\"title\":\"Import\"}\n```" }), "stop");
+        assert_eq!(tool_input(fenced, "record").unwrap()["title"], "Import");
+
+        let cut_off = reply(json!({ "content": "{\"title\":" }), "length");
+        assert!(matches!(
+            tool_input(cut_off, "record"),
+            Err(ClaudeError::Parse(_))
+        ));
+
+        let provider = OpenAiCompatibleProvider::new(
+            "http://localhost:8080/v1/",
+            None,
+            "qwen2.5-coder".to_string(),
+            &ApiSettings::default(),
+        )
+        .unwrap();
+        let request = provider.request(
+            &[SystemBlock::text("Rules"), SystemBlock::cached("Repo")],
+            "Feature #1",
+            256,
+        );
+        assert_eq!(provider.base_url, "http://localhost:8080/v1");
+        assert_eq!(request.messages[0].content, "Rules\n\nRepo");
+        assert!(serde_json::to_value(&request).unwrap().get("stream").is_none());
+    }
+}
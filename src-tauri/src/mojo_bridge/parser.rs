@@ -3,9 +3,12 @@ use std::path::Path;
 
 use crate::types::ProjectData;
 
+use super::protocol::read_output;
+
+/// Read the engine's output file; see `protocol::read_output`.
 pub fn parse_preprocessed_output(path: &Path) -> Result<ProjectData, String> {
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read output file: {}", e))?;
 
-    serde_json::from_str(&content).map_err(|e| format!("Failed to parse JSON output: {}", e))
+    read_output(&content)
 }
@@ -0,0 +1,156 @@
+use serde::Deserialize;
+
+use crate::types::{EngineInfo, ProjectData, ScanProgress};
+
+/// Newest protocol version the bridge speaks, passed to the engine with
+/// `--protocol`. The engine's first stdout line is a `hello` with the version
+/// it picked, its own version and its capabilities; `progress` lines and an
+/// optional `error` follow. The output file carries the same engine info
+/// around the `ProjectData`. Unknown fields and line types are ignored, so
+/// additions don't need a new version.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Oldest protocol version the bridge still reads.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// One line of the engine's stdout.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum EngineMessage {
+    Hello(EngineInfo),
+    Progress(ScanProgress),
+    /// The engine gave up; it exits with a failure status after this
+    Error {
+        message: String,
+    },
+    /// A line type added by a newer engine
+    #[serde(other)]
+    Unknown,
+}
+
+/// Parse a stdout line; `None` for lines outside the protocol.
+pub fn parse_message(line: &str) -> Option<EngineMessage> {
+    serde_json::from_str(line).ok()
+}
+
+/// Error for an engine that doesn't speak the protocol at all.
+pub fn legacy_engine_error() -> String {
+    format!(
+        "The Mojo engine did not announce a protocol version, so it predates protocol {}. \
+         Rebuild it with scripts/build-mojo.sh.",
+        MIN_PROTOCOL_VERSION
+    )
+}
+
+/// Check that the bridge can read the engine's protocol version.
+pub fn check_compatible(engine: &EngineInfo) -> Result<(), String> {
+    let version = engine.protocol_version;
+    if version < MIN_PROTOCOL_VERSION {
+        return Err(format!(
+            "Mojo engine {} speaks protocol version {}, but CodeLens needs {} to {}. \
+             Rebuild the engine with scripts/build-mojo.sh.",
+            engine.engine_version, version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ));
+    }
+    if version > PROTOCOL_VERSION {
+        return Err(format!(
+            "Mojo engine {} speaks protocol version {}, but CodeLens only reads up to {}. \
+             Update CodeLens or use a matching engine.",
+            engine.engine_version, version, PROTOCOL_VERSION
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct EngineOutput {
+    project: ProjectData,
+}
+
+/// Read the engine's output file. The version is checked before the project,
+/// and schema errors name the field they were found at.
+pub fn read_output(json: &str) -> Result<ProjectData, String> {
+    let engine: EngineInfo = serde_json::from_str(json).map_err(|e| {
+        if e.is_data() {
+            legacy_engine_error()
+        } else {
+            format!("Mojo engine output is not valid JSON: {}", e)
+        }
+    })?;
+    check_compatible(&engine)?;
+
+    let deserializer = &mut serde_json::Deserializer::from_str(json);
+    let output: EngineOutput = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        format!(
+            "Mojo engine {} wrote invalid protocol {} output at {}: {}",
+            engine.engine_version,
+            engine.protocol_version,
+            e.path(),
+            e.inner()
+        )
+    })?;
+
+    let mut project = output.project;
+    project.engine = Some(engine);
+    Ok(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty project with a field from a newer engine.
+    fn project() -> String {
+        let mut project = serde_json::to_value(ProjectData::default()).unwrap();
+        project["analytics"]["futureMetric"] = 1.into();
+        project.to_string()
+    }
+
+    fn output(protocol_version: u32, project: &str) -> String {
+        format!(
+            r#"{{"protocolVersion": {}, "engineVersion": "0.1.0",
+                "capabilities": ["embeddings"], "buildId": "abc", "project": {}}}"#,
+            protocol_version, project
+        )
+    }
+
+    #[test]
+    fn versions_are_negotiated_and_additions_tolerated() {
+        let hello = r#"{"type": "hello", "protocolVersion": 1, "engineVersion": "0.1.0",
+            "capabilities": ["embeddings", "patterns"], "gpu": true}"#;
+        let Some(EngineMessage::Hello(engine)) = parse_message(hello) else {
+            panic!("hello not parsed");
+        };
+        assert_eq!(engine.capabilities, ["embeddings", "patterns"]);
+        assert!(check_compatible(&engine).is_ok());
+
+        let progress = r#"{"type": "progress", "stage": "parsing", "progress": 0.3,
+            "message": "Parsed 150 commits", "etaSecs": 4}"#;
+        assert!(matches!(
+            parse_message(progress),
+            Some(EngineMessage::Progress(p)) if p.stage == "parsing"
+        ));
+        assert!(matches!(
+            parse_message(r#"{"type": "heartbeat"}"#),
+            Some(EngineMessage::Unknown)
+        ));
+        // Lines from engines before the protocol, and stray output
+        assert!(parse_message(r#"{"stage": "init", "progress": 0.0, "message": ""}"#).is_none());
+        assert!(parse_message("loading model").is_none());
+
+        let newer = EngineInfo {
+            protocol_version: PROTOCOL_VERSION + 1,
+            ..engine
+        };
+        assert!(check_compatible(&newer).unwrap_err().contains("Update CodeLens"));
+
+        let project = project();
+        let read = read_output(&output(1, &project)).unwrap();
+        assert_eq!(read.engine.unwrap().engine_version, "0.1.0");
+        assert!(read_output(&project).unwrap_err().contains("predates protocol"));
+        assert!(read_output(&output(2, &project)).unwrap_err().contains("version 2"));
+
+        let broken = project.replace(r#""commits":[]"#, r#""commits":[{"hash":5}]"#);
+        let error = read_output(&output(1, &broken)).unwrap_err();
+        assert!(error.contains("at project.commits[0].hash"), "{}", error);
+    }
+}
@@ -5,7 +5,14 @@ use std::thread;
 
 use tauri::Manager;
 
-use crate::types::ScanProgress;
+use crate::types::{AppSettings, EngineInfo, ScanProgress};
+
+use super::protocol::{
+    check_compatible, legacy_engine_error, parse_message, EngineMessage, PROTOCOL_VERSION,
+};
+
+/// Models directory when the settings name none.
+const DEFAULT_MODELS_DIR: &str = "./mojo-engine/src/models";
 
 /// Resolve the path to the Mojo engine binary.
 ///
@@ -67,16 +74,18 @@ fn resolve_engine_path(app_handle: Option<&tauri::AppHandle>) -> Option<PathBuf>
 
 /// Run the Mojo engine as a subprocess, streaming progress to the callback.
 ///
-/// Falls back gracefully if the engine binary is not found.
+/// The models, commit limit, clustering and embedding batch size come from
+/// `settings`. Falls back gracefully if the engine binary is not found, and
+/// stops an engine whose protocol version the bridge can't read. Returns what
+/// the engine announced.
 pub fn run_mojo_engine(
     repo_path: &str,
     output_path: &str,
-    models_dir: &str,
+    settings: &AppSettings,
     verbose: bool,
-    max_commits: u32,
     on_progress: impl Fn(ScanProgress),
     app_handle: Option<&tauri::AppHandle>,
-) -> Result<(), String> {
+) -> Result<EngineInfo, String> {
     let engine_path = resolve_engine_path(app_handle).ok_or_else(|| {
         "Mojo engine binary not found. Falling back to Rust pipeline.".to_string()
     })?;
@@ -87,9 +96,17 @@ pub fn run_mojo_engine(
         .arg("--output")
         .arg(output_path)
         .arg("--models-dir")
-        .arg(models_dir)
+        .arg(settings.models_dir.as_deref().unwrap_or(DEFAULT_MODELS_DIR))
         .arg("--max-commits")
-        .arg(max_commits.to_string());
+        .arg(settings.max_commits.to_string())
+        .arg("--eps")
+        .arg(settings.clustering_eps.to_string())
+        .arg("--min-samples")
+        .arg(settings.clustering_min_samples.to_string())
+        .arg("--embedding-batch-size")
+        .arg(settings.embedding_batch_size.to_string())
+        .arg("--protocol")
+        .arg(PROTOCOL_VERSION.to_string());
 
     if verbose {
         cmd.arg("--verbose");
@@ -111,12 +128,38 @@ pub fn run_mojo_engine(
         });
     }
 
+    let mut engine: Option<EngineInfo> = None;
+    let mut engine_error: Option<String> = None;
     if let Some(stdout) = child.stdout.take() {
         let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                if let Ok(progress) = serde_json::from_str::<ScanProgress>(&line) {
-                    on_progress(progress);
+        for line in reader.lines().map_while(Result::ok) {
+            let message = parse_message(&line);
+            // Stray output before the hello (a runtime banner, say) is skipped;
+            // an engine that never sends one is reported as legacy on exit
+            let rejected = match (&message, &engine) {
+                (Some(EngineMessage::Hello(info)), None) => check_compatible(info).err(),
+                _ => None,
+            };
+            if let Some(e) = rejected {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+
+            match message {
+                Some(EngineMessage::Hello(info)) => {
+                    log::info!(
+                        "Mojo engine {} (protocol {}, capabilities: {})",
+                        info.engine_version,
+                        info.protocol_version,
+                        info.capabilities.join(", ")
+                    );
+                    engine = Some(info);
+                }
+                Some(EngineMessage::Progress(progress)) => on_progress(progress),
+                Some(EngineMessage::Error { message }) => engine_error = Some(message),
+                Some(EngineMessage::Unknown) | None => {
+                    log::debug!("Ignoring engine output: {}", line);
                 }
             }
         }
@@ -126,11 +169,15 @@ pub fn run_mojo_engine(
         .wait()
         .map_err(|e| format!("Mojo engine failed: {}", e))?;
 
+    if let Some(message) = engine_error {
+        return Err(format!("Mojo engine error: {}", message));
+    }
+    let engine = engine.ok_or_else(legacy_engine_error)?;
     if !status.success() {
         return Err(format!("Mojo engine exited with status: {}", status));
     }
 
-    Ok(())
+    Ok(engine)
 }
 
 /// Check if the Mojo engine binary is available.
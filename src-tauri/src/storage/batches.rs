@@ -0,0 +1,111 @@
+use rusqlite::params;
+
+use super::db::Database;
+use crate::types::EnrichmentBatch;
+
+impl Database {
+    /// Insert or update a stored enrichment batch.
+    pub fn save_batch(&self, batch: &EnrichmentBatch) -> Result<(), String> {
+        let json = serde_json::to_string(batch)
+            .map_err(|e| format!("Failed to serialize batch: {}", e))?;
+        self.conn()
+            .execute(
+                "INSERT OR REPLACE INTO enrichment_batches (batch_id, project_id, status, created_at, data_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    batch.batch_id,
+                    batch.project_id,
+                    batch.status,
+                    batch.created_at,
+                    json
+                ],
+            )
+            .map_err(|e| format!("Batch write failed: {}", e))?;
+        Ok(())
+    }
+
+    /// Batches whose results are not applied yet, oldest first.
+    pub fn pending_batches(&self) -> Result<Vec<EnrichmentBatch>, String> {
+        self.query_batches(
+            "SELECT data_json FROM enrichment_batches WHERE status != 'applied'
+             ORDER BY created_at",
+            params![],
+        )
+    }
+
+    /// All batches of a project, newest first.
+    pub fn list_batches(&self, project_id: &str) -> Result<Vec<EnrichmentBatch>, String> {
+        self.query_batches(
+            "SELECT data_json FROM enrichment_batches WHERE project_id = ?1
+             ORDER BY created_at DESC",
+            params![project_id],
+        )
+    }
+
+    fn query_batches(
+        &self,
+        sql: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<EnrichmentBatch>, String> {
+        let mut stmt = self
+            .conn()
+            .prepare(sql)
+            .map_err(|e| format!("Batch query failed: {}", e))?;
+        let rows = stmt
+            .query_map(params, |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Batch query failed: {}", e))?;
+
+        let mut batches = Vec::new();
+        for json in rows.flatten() {
+            match serde_json::from_str::<EnrichmentBatch>(&json) {
+                Ok(batch) => batches.push(batch),
+                Err(e) => log::warn!("Skipping unreadable enrichment batch: {}", e),
+            }
+        }
+        Ok(batches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(id: &str, status: &str, created_at: &str) -> EnrichmentBatch {
+        EnrichmentBatch {
+            batch_id: id.to_string(),
+            project_id: "p1".to_string(),
+            model: "claude-test".to_string(),
+            status: status.to_string(),
+            created_at: created_at.to_string(),
+            items: Vec::new(),
+            processing: 0,
+            succeeded: 0,
+            errored: 0,
+            expired: 0,
+            applied: 0,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn applied_batches_are_no_longer_pending() {
+        let dir = std::env::temp_dir().join(format!("codelens-batches-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::open(&dir.join("test.db")).unwrap();
+
+        db.save_batch(&batch("b2", "in_progress", "2026-01-02T00:00:00Z"))
+            .unwrap();
+        db.save_batch(&batch("b1", "ended", "2026-01-01T00:00:00Z"))
+            .unwrap();
+        db.save_batch(&batch("b2", "applied", "2026-01-02T00:00:00Z"))
+            .unwrap();
+
+        let ids = |batches: Vec<EnrichmentBatch>| -> Vec<String> {
+            batches.into_iter().map(|b| b.batch_id).collect()
+        };
+        assert_eq!(ids(db.pending_batches().unwrap()), ["b1"]);
+        assert_eq!(ids(db.list_batches("p1").unwrap()), ["b2", "b1"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
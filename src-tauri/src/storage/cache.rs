@@ -1,25 +1,81 @@
+use std::time::Duration;
+
 use rusqlite::params;
 
 use super::db::Database;
 
 impl Database {
+    /// Cached value for `key`, unless it has expired.
     pub fn cache_get(&self, key: &str) -> Option<String> {
         self.conn()
             .query_row(
-                "SELECT value FROM cache WHERE key = ?1",
+                "SELECT value FROM cache
+                 WHERE key = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))",
                 params![key],
                 |row| row.get(0),
             )
             .ok()
     }
 
-    pub fn cache_set(&self, key: &str, value: &str) -> Result<(), String> {
+    /// Store `value` under `key`. With a `ttl` the entry expires after that long.
+    pub fn cache_set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<(), String> {
+        let expires = ttl.map(|ttl| format!("+{} seconds", ttl.as_secs()));
         self.conn()
             .execute(
-                "INSERT OR REPLACE INTO cache (key, value, created_at) VALUES (?1, ?2, datetime('now'))",
-                params![key, value],
+                "INSERT OR REPLACE INTO cache (key, value, created_at, expires_at)
+                 VALUES (?1, ?2, datetime('now'), datetime('now', ?3))",
+                params![key, value, expires],
             )
             .map_err(|e| format!("Cache write failed: {}", e))?;
         Ok(())
     }
+
+    /// Drop expired entries, then the oldest ones beyond `max_entries`.
+    /// Returns the number of entries removed.
+    pub fn cache_evict(&self, max_entries: usize) -> Result<usize, String> {
+        let expired = self
+            .conn()
+            .execute(
+                "DELETE FROM cache WHERE expires_at IS NOT NULL AND expires_at <= datetime('now')",
+                [],
+            )
+            .map_err(|e| format!("Cache eviction failed: {}", e))?;
+        let overflow = self
+            .conn()
+            .execute(
+                "DELETE FROM cache WHERE key IN (
+                     SELECT key FROM cache ORDER BY created_at DESC LIMIT -1 OFFSET ?1
+                 )",
+                params![max_entries as i64],
+            )
+            .map_err(|e| format!("Cache eviction failed: {}", e))?;
+        Ok(expired + overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_and_overflowing_entries_are_evicted() {
+        let dir = std::env::temp_dir().join(format!("codelens-cache-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::open(&dir.join("test.db")).unwrap();
+
+        db.cache_set("stale", "1", Some(Duration::ZERO)).unwrap();
+        db.cache_set("fresh", "2", Some(Duration::from_secs(3600))).unwrap();
+        db.cache_set("forever", "3", None).unwrap();
+        assert_eq!(db.cache_get("stale"), None);
+        assert_eq!(db.cache_get("fresh").as_deref(), Some("2"));
+
+        assert_eq!(db.cache_evict(1).unwrap(), 2);
+        let remaining: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -1,11 +1,27 @@
 use rusqlite::{Connection, Result as SqlResult};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub struct Database {
     conn: Connection,
 }
 
+/// App-managed data directory: `<data_dir>/codelens`.
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    let dir = dirs::data_dir()
+        .ok_or("Could not determine the app data directory")?
+        .join("codelens");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
 impl Database {
+    /// Open (or create) the app database at `<data_dir>/codelens/codelens.db`.
+    pub fn open_default() -> Result<Self, String> {
+        let path = app_data_dir()?.join("codelens.db");
+        Self::open(&path).map_err(|e| format!("Failed to open database: {}", e))
+    }
+
     pub fn open(path: &Path) -> SqlResult<Self> {
         let conn = Connection::open(path)?;
         let db = Self { conn };
@@ -27,8 +43,34 @@ impl Database {
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL,
                 created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS session_snapshots (
+                archive_id TEXT PRIMARY KEY,
+                repo_path TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                sessions_json TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS enrichment_batches (
+                batch_id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                data_json TEXT NOT NULL
             );",
         )?;
+
+        // Databases created before cache entries could expire lack `expires_at`
+        let has_expiry: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('cache') WHERE name = 'expires_at'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_expiry {
+            self.conn
+                .execute_batch("ALTER TABLE cache ADD COLUMN expires_at TEXT;")?;
+        }
         Ok(())
     }
 
@@ -1,2 +1,7 @@
+pub mod batches;
 pub mod cache;
 pub mod db;
+pub mod projects;
+pub mod secrets;
+pub mod settings;
+pub mod snapshots;
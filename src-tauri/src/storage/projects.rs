@@ -0,0 +1,117 @@
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+use super::db::Database;
+use crate::types::{ProjectData, ProjectSummary};
+
+fn to_json(project: &ProjectData) -> Result<String, String> {
+    serde_json::to_string(project).map_err(|e| format!("Failed to serialize project: {}", e))
+}
+
+impl Database {
+    /// Store a freshly scanned project, replacing the previous scan of the same
+    /// repo. Reuses the repo's project id, or assigns one, and sets `project.id`.
+    pub fn save_project(&self, project: &mut ProjectData) -> Result<String, String> {
+        let existing: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT id FROM projects WHERE path = ?1",
+                params![project.repository.path],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Project query failed: {}", e))?;
+        let id = existing.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        project.id = Some(id.clone());
+
+        self.conn()
+            .execute(
+                "INSERT OR REPLACE INTO projects (id, name, path, last_scanned, data_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    id,
+                    project.repository.name,
+                    project.repository.path,
+                    Utc::now().to_rfc3339(),
+                    to_json(project)?
+                ],
+            )
+            .map_err(|e| format!("Project write failed: {}", e))?;
+        Ok(id)
+    }
+
+    /// Overwrite a stored project's data without touching its scan time.
+    pub fn update_project(&self, id: &str, project: &ProjectData) -> Result<(), String> {
+        let updated = self
+            .conn()
+            .execute(
+                "UPDATE projects SET data_json = ?2 WHERE id = ?1",
+                params![id, to_json(project)?],
+            )
+            .map_err(|e| format!("Project write failed: {}", e))?;
+        if updated == 0 {
+            return Err(format!("Project not found: {}", id));
+        }
+        Ok(())
+    }
+
+    pub fn load_project(&self, id: &str) -> Result<ProjectData, String> {
+        let json: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT data_json FROM projects WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Project query failed: {}", e))?
+            .flatten();
+        let json = json.ok_or_else(|| format!("Project not found: {}", id))?;
+
+        let mut project: ProjectData = serde_json::from_str(&json)
+            .map_err(|e| format!("Stored project {} is unreadable: {}", id, e))?;
+        project.id = Some(id.to_string());
+        Ok(project)
+    }
+
+    /// Summaries of all stored projects, most recently scanned first.
+    pub fn list_projects(&self) -> Result<Vec<ProjectSummary>, String> {
+        let mut stmt = self
+            .conn()
+            .prepare(
+                "SELECT id, name, path, last_scanned, data_json FROM projects
+                 ORDER BY last_scanned DESC",
+            )
+            .map_err(|e| format!("Project query failed: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })
+            .map_err(|e| format!("Project query failed: {}", e))?;
+
+        let mut projects = Vec::new();
+        for (id, name, path, last_scanned, json) in rows.flatten() {
+            let Some(data) = json.and_then(|j| serde_json::from_str::<ProjectData>(&j).ok())
+            else {
+                log::warn!("Skipping unreadable stored project {}", id);
+                continue;
+            };
+            projects.push(ProjectSummary {
+                id,
+                name,
+                path,
+                last_scanned,
+                total_commits: data.repository.total_commits,
+                total_features: data.features.len() as u32,
+                claude_code_percentage: data.analytics.claude_code_commit_percentage,
+            });
+        }
+        Ok(projects)
+    }
+}
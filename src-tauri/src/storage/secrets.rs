@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use super::db::app_data_dir;
+use super::settings::{settings_path, write_private_file};
+
+/// Keyring service all CodeLens secrets are stored under.
+const KEYRING_SERVICE: &str = "codelens";
+/// Name of the Claude API key in the secret store.
+pub const API_KEY_SECRET: &str = "anthropic-api-key";
+
+static KEYRING_AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+/// Somewhere to keep secrets outside the settings file.
+pub trait SecretStore: Send + Sync {
+    /// How the store is shown to the user: "keyring" or "encryptedFile".
+    fn kind(&self) -> &'static str;
+
+    fn get(&self, name: &str) -> Result<Option<String>, String>;
+
+    fn set(&self, name: &str, value: &str) -> Result<(), String>;
+
+    /// Remove a secret; removing a missing one is not an error.
+    fn delete(&self, name: &str) -> Result<(), String>;
+}
+
+/// The platform credential store: the Secret Service (GNOME Keyring, KWallet)
+/// on Linux, the Keychain on macOS and the Credential Manager on Windows.
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(name: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(KEYRING_SERVICE, name).map_err(|e| format!("Keyring error: {}", e))
+    }
+
+    /// Whether the platform store answers, which it doesn't on headless
+    /// Linux boxes without a Secret Service. Checked once per run.
+    pub fn is_available() -> bool {
+        *KEYRING_AVAILABLE.get_or_init(|| {
+            let probe = Self::entry(API_KEY_SECRET).map(|entry| entry.get_password());
+            match probe {
+                Ok(Ok(_)) | Ok(Err(keyring::Error::NoEntry)) => true,
+                Ok(Err(e)) => {
+                    log::info!("Keyring unavailable, using the encrypted file: {}", e);
+                    false
+                }
+                Err(e) => {
+                    log::info!("{}, using the encrypted file", e);
+                    false
+                }
+            }
+        })
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn kind(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>, String> {
+        match Self::entry(name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Failed to read {} from the keyring: {}", name, e)),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<(), String> {
+        Self::entry(name)?
+            .set_password(value)
+            .map_err(|e| format!("Failed to save {} in the keyring: {}", name, e))
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        match Self::entry(name)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to remove {} from the keyring: {}", name, e)),
+        }
+    }
+}
+
+/// One AES-256-GCM sealed secret, authenticated with its name.
+#[derive(Debug, Serialize, Deserialize)]
+struct SealedSecret {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secrets in a JSON file next to the settings, sealed with a random key kept
+/// in the app data directory. Both files are readable by the user only.
+///
+/// Keeping the key apart means a copied or synced config directory doesn't
+/// expose the secrets; it does not protect them from the user's own account.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: PathBuf, key_path: PathBuf) -> Self {
+        Self { path, key_path }
+    }
+
+    /// `<config_dir>/codelens/secrets.json`, sealed with
+    /// `<data_dir>/codelens/secrets.key`.
+    pub fn open_default() -> Result<Self, String> {
+        let path = settings_path()?.with_file_name("secrets.json");
+        Ok(Self::new(path, app_data_dir()?.join("secrets.key")))
+    }
+
+    fn read(&self) -> Result<HashMap<String, SealedSecret>, String> {
+        if !self.path.exists() {
+            return Ok(HashMap::new());
+        }
+        let json = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid secrets file {}: {}", self.path.display(), e))
+    }
+
+    fn write(&self, secrets: &HashMap<String, SealedSecret>) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(secrets)
+            .map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+        write_private_file(&self.path, json.as_bytes())
+    }
+
+    /// The sealing key, created on first use when `create` is set.
+    fn cipher(&self, create: bool) -> Result<Aes256Gcm, String> {
+        if !self.key_path.exists() {
+            if !create {
+                return Err(format!(
+                    "Secrets key {} is missing; set the API key again",
+                    self.key_path.display()
+                ));
+            }
+            let key = Aes256Gcm::generate_key(OsRng);
+            write_private_file(&self.key_path, hex::encode(key).as_bytes())?;
+        }
+        let key = read_key(&self.key_path)?;
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+}
+
+fn read_key(path: &Path) -> Result<Vec<u8>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    match hex::decode(text.trim()) {
+        Ok(key) if key.len() == 32 => Ok(key),
+        _ => Err(format!("Invalid secrets key {}", path.display())),
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn kind(&self) -> &'static str {
+        "encryptedFile"
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>, String> {
+        let secrets = self.read()?;
+        let Some(sealed) = secrets.get(name) else {
+            return Ok(None);
+        };
+        let invalid = || format!("Cannot decrypt {} in {}", name, self.path.display());
+        let nonce = hex::decode(&sealed.nonce).map_err(|_| invalid())?;
+        let ciphertext = hex::decode(&sealed.ciphertext).map_err(|_| invalid())?;
+        if nonce.len() != 12 {
+            return Err(invalid());
+        }
+        let plaintext = self
+            .cipher(false)?
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| invalid())?;
+        String::from_utf8(plaintext).map(Some).map_err(|_| invalid())
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<(), String> {
+        let mut secrets = self.read()?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher(true)?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| format!("Failed to encrypt {}", name))?;
+        secrets.insert(
+            name.to_string(),
+            SealedSecret {
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            },
+        );
+        self.write(&secrets)
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        let mut secrets = self.read()?;
+        if secrets.remove(name).is_some() {
+            self.write(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+/// The secret store of this machine. `CODELENS_SECRET_STORE` picks one:
+/// "keyring", or "file" for the encrypted file. By default the keyring is
+/// used when it answers, and the encrypted file otherwise.
+pub fn secret_store() -> Result<Box<dyn SecretStore>, String> {
+    let choice = std::env::var("CODELENS_SECRET_STORE").unwrap_or_default();
+    match choice.trim().to_lowercase().as_str() {
+        "keyring" => Ok(Box::new(KeyringStore)),
+        "file" => Ok(Box::new(EncryptedFileStore::open_default()?)),
+        "" if KeyringStore::is_available() => Ok(Box::new(KeyringStore)),
+        "" => Ok(Box::new(EncryptedFileStore::open_default()?)),
+        other => Err(format!(
+            "Unknown CODELENS_SECRET_STORE \"{}\": expected \"keyring\" or \"file\"",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_file_round_trips_without_plaintext() {
+        let dir = std::env::temp_dir().join(format!("codelens-secrets-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("secrets.json");
+        let store = EncryptedFileStore::new(path.clone(), dir.join("secrets.key"));
+
+        assert_eq!(store.get(API_KEY_SECRET).unwrap(), None);
+        store.set(API_KEY_SECRET, "sk-ant-first").unwrap();
+        store.set(API_KEY_SECRET, "sk-ant-second").unwrap();
+        store.set("other", "value").unwrap();
+        assert_eq!(store.get(API_KEY_SECRET).unwrap().as_deref(), Some("sk-ant-second"));
+        assert!(!fs::read_to_string(&path).unwrap().contains("sk-ant"));
+
+        // A secret moved to another name fails authentication
+        let mut secrets = store.read().unwrap();
+        let moved = secrets.remove(API_KEY_SECRET).unwrap();
+        secrets.insert("other".to_string(), moved);
+        store.write(&secrets).unwrap();
+        assert!(store.get("other").is_err());
+
+        store.delete("other").unwrap();
+        store.delete("other").unwrap();
+        assert_eq!(store.get("other").unwrap(), None);
+
+        // Without its key the file can't be read
+        store.set(API_KEY_SECRET, "sk-ant-third").unwrap();
+        fs::remove_file(dir.join("secrets.key")).unwrap();
+        assert!(store.get(API_KEY_SECRET).unwrap_err().contains("missing"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::types::AppSettings;
+
+/// Settings file: `<config_dir>/codelens/settings.json`, next to the pricing
+/// override.
+pub fn settings_path() -> Result<PathBuf, String> {
+    Ok(dirs::config_dir()
+        .ok_or("Could not determine the app config directory")?
+        .join("codelens")
+        .join("settings.json"))
+}
+
+/// Saved settings, or the defaults before the first save.
+pub fn load_settings() -> Result<AppSettings, String> {
+    load_settings_from(&settings_path()?)
+}
+
+pub fn load_settings_from(path: &Path) -> Result<AppSettings, String> {
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid settings file {}: {}", path.display(), e))
+}
+
+pub fn save_settings(settings: &AppSettings) -> Result<(), String> {
+    save_settings_to(&settings_path()?, settings)
+}
+
+pub fn save_settings_to(path: &Path, settings: &AppSettings) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    write_private_file(path, json.as_bytes())
+}
+
+/// Write a file only the user may read, through a temporary file so a crash
+/// never leaves a truncated file behind.
+pub fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict {}: {}", tmp.display(), e))?;
+    }
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,56 @@
+use rusqlite::params;
+
+use super::db::Database;
+use crate::types::PromptSession;
+
+impl Database {
+    /// Store the parsed sessions of transcripts moved to the trash, keyed by archive.
+    pub fn save_session_snapshot(
+        &self,
+        archive_id: &str,
+        repo_path: &str,
+        sessions: &[PromptSession],
+    ) -> Result<(), String> {
+        let json = serde_json::to_string(sessions)
+            .map_err(|e| format!("Failed to serialize sessions: {}", e))?;
+        self.conn()
+            .execute(
+                "INSERT OR REPLACE INTO session_snapshots (archive_id, repo_path, created_at, sessions_json)
+                 VALUES (?1, ?2, datetime('now'), ?3)",
+                params![archive_id, repo_path, json],
+            )
+            .map_err(|e| format!("Snapshot write failed: {}", e))?;
+        Ok(())
+    }
+
+    /// All snapshotted sessions for a repo.
+    pub fn load_session_snapshots(&self, repo_path: &str) -> Result<Vec<PromptSession>, String> {
+        let mut stmt = self
+            .conn()
+            .prepare("SELECT sessions_json FROM session_snapshots WHERE repo_path = ?1")
+            .map_err(|e| format!("Snapshot query failed: {}", e))?;
+        let rows = stmt
+            .query_map(params![repo_path], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Snapshot query failed: {}", e))?;
+
+        let mut sessions = Vec::new();
+        for json in rows.flatten() {
+            match serde_json::from_str::<Vec<PromptSession>>(&json) {
+                Ok(mut s) => sessions.append(&mut s),
+                Err(e) => log::warn!("Skipping unreadable session snapshot: {}", e),
+            }
+        }
+        Ok(sessions)
+    }
+
+    /// Drop the snapshot of an archive once its transcripts are restored.
+    pub fn delete_session_snapshot(&self, archive_id: &str) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "DELETE FROM session_snapshots WHERE archive_id = ?1",
+                params![archive_id],
+            )
+            .map_err(|e| format!("Snapshot delete failed: {}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Version of the session bundle format written by `export_sessions`.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub bundle_id: String,
+    pub created_at: String,
+    /// Name of the repository the sessions were exported from
+    pub repository: String,
+    pub session_ids: Vec<String>,
+    pub feature_ids: Vec<i32>,
+    pub transcript_count: u32,
+    pub prompt_count: u32,
+    pub redacted: bool,
+    /// Redactions applied to the bundled transcripts, per detector
+    pub redaction_counts: HashMap<String, u32>,
+}
+
+/// One Claude Code JSONL transcript, stored verbatim (or redacted).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundledTranscript {
+    pub transcript_id: String,
+    /// Logical session id the transcript belongs to
+    pub session_id: String,
+    pub content: String,
+}
+
+/// Portable bundle of sessions, written as gzipped JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBundle {
+    pub manifest: BundleManifest,
+    pub transcripts: Vec<BundledTranscript>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSessionsResult {
+    pub manifest: BundleManifest,
+    pub imported_prompts: u32,
+}
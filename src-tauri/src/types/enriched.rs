@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::{CommitData, FeatureCluster, PromptSession};
+use super::{
+    CommitData, FeatureCluster, IntentAnalysis, ProjectConfigSource, PromptSession,
+    RedactionReport, TokenUsage,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RepositoryInfo {
     pub path: String,
@@ -13,21 +16,58 @@ pub struct RepositoryInfo {
     pub languages_detected: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DateRange {
     pub start: String,
     pub end: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectData {
+    /// Id of the stored project; `None` until the scan is saved
+    #[serde(default)]
+    pub id: Option<String>,
     pub repository: RepositoryInfo,
     pub commits: Vec<CommitData>,
     pub features: Vec<FeatureCluster>,
     pub prompt_sessions: Vec<PromptSession>,
     pub analytics: Analytics,
+    #[serde(default)]
+    pub subprojects: Vec<SubprojectSummary>,
+    #[serde(default)]
+    pub redaction: RedactionReport,
+    /// Per-prompt completion, re-prompt and outcome analysis
+    #[serde(default)]
+    pub intent_analyses: Vec<IntentAnalysis>,
+    /// The repo's `.codelens.toml`, when the scan found one
+    #[serde(default)]
+    pub project_config: Option<ProjectConfigSource>,
+    /// The Mojo engine that produced the scan; `None` for the Rust fallback
+    #[serde(default)]
+    pub engine: Option<EngineInfo>,
+}
+
+/// A logical project inside a monorepo, identified by repo-relative path prefixes
+/// (e.g. `services/billing`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subproject {
+    pub name: String,
+    pub paths: Vec<String>,
+}
+
+/// Per-subproject roll-up stored on the repo-level `ProjectData`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubprojectSummary {
+    pub name: String,
+    pub paths: Vec<String>,
+    pub commit_hashes: Vec<String>,
+    pub feature_ids: Vec<i32>,
+    pub total_prompts: u32,
+    pub analytics: Analytics,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,9 +76,11 @@ pub struct WeekVelocity {
     pub week: String,
     pub features: u32,
     pub commits: u32,
+    #[serde(default)]
+    pub cost_usd: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Analytics {
     pub total_features: u32,
@@ -59,6 +101,13 @@ pub struct Analytics {
     pub pattern_count: u32,
     #[serde(default)]
     pub embedding_coverage: f32,
+    // Cost accounting from session token usage
+    #[serde(default)]
+    pub total_cost_usd: f64,
+    #[serde(default)]
+    pub avg_cost_per_feature: f64,
+    #[serde(default)]
+    pub pricing_version: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,6 +130,93 @@ pub struct ScanProgress {
     pub message: String,
 }
 
+/// What the Mojo engine announces when it starts and repeats in its output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineInfo {
+    /// Version of the engine protocol both sides agreed on
+    pub protocol_version: u32,
+    pub engine_version: String,
+    /// Optional stages the engine can run, e.g. `embeddings`
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// Emitted as `intent-progress` after each prompt's intent extraction finishes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntentProgress {
+    pub session_id: String,
+    pub timestamp: String,
+    /// "extracted" or "failed"
+    pub status: String,
+    pub goal: Option<String>,
+    pub error: Option<String>,
+    pub completed: u32,
+    pub total: u32,
+}
+
+/// Emitted as `enrichment-progress` after each feature's enrichment finishes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrichmentProgress {
+    pub cluster_id: i32,
+    /// "enriched" or "failed"
+    pub status: String,
+    pub title: Option<String>,
+    pub error: Option<String>,
+    pub completed: u32,
+    pub total: u32,
+    /// API tokens used by the run so far, including prompt cache writes and reads
+    #[serde(default)]
+    pub usage: TokenUsage,
+    /// USD saved so far by reading the shared preamble from the prompt cache
+    #[serde(default)]
+    pub cache_savings_usd: f64,
+}
+
+/// A feature enrichment run submitted to the Message Batches API. Stored so
+/// that batches are followed up across app restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrichmentBatch {
+    pub batch_id: String,
+    pub project_id: String,
+    pub model: String,
+    /// "in_progress", "canceling" or "ended" as reported by the API, then
+    /// "applied" once the results are stored in the project
+    pub status: String,
+    pub created_at: String,
+    pub items: Vec<EnrichmentBatchItem>,
+    pub processing: u32,
+    pub succeeded: u32,
+    /// Requests that errored, or whose reply was not a valid enrichment
+    pub errored: u32,
+    /// Requests canceled or left unprocessed when the batch expired
+    pub expired: u32,
+    /// Features of the stored project that received their enrichment
+    pub applied: u32,
+    pub error: Option<String>,
+}
+
+/// One feature in an enrichment batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrichmentBatchItem {
+    pub custom_id: String,
+    pub cluster_id: i32,
+    /// Enrichment cache key of the feature at submission, where its result is cached
+    pub cache_key: String,
+}
+
+/// Emitted as `narrative-delta` for each piece of a streamed feature narrative.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NarrativeDelta {
+    pub cluster_id: i32,
+    pub text: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResults {
@@ -97,15 +233,61 @@ pub struct FunctionHistory {
     pub modifications: Vec<CommitData>,
 }
 
+/// User settings, saved in `<config_dir>/codelens/settings.json`. Missing
+/// fields take their default.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct AppSettings {
+    /// Never saved or sent to the frontend: the key lives in the secret store
+    /// and is filled in by `resolved_settings`. Still read from older
+    /// settings files, to move it into the store.
+    #[serde(skip_serializing)]
     pub api_key: Option<String>,
+    /// Where enrichment runs: "anthropic" or "openai" for an OpenAI-compatible server
+    pub provider: String,
+    /// API root of the OpenAI-compatible server
+    pub openai_base_url: String,
+    /// Model the OpenAI-compatible server runs; required with the "openai" provider
+    pub openai_model: Option<String>,
+    /// Only ever set from `OPENAI_API_KEY`; local servers usually need none
+    #[serde(skip)]
+    pub openai_api_key: Option<String>,
     pub claude_model: String,
     pub max_concurrent_api_calls: u32,
+    /// Commits the Mojo engine embeds per inference call
     pub embedding_batch_size: u32,
+    /// DBSCAN neighbourhood radius, in cosine distance
     pub clustering_eps: f32,
     pub clustering_min_samples: u32,
+    /// Newest commits a scan reads
+    pub max_commits: u32,
+    /// Models of the Mojo engine; `None` uses `./mojo-engine/src/models`
+    pub models_dir: Option<String>,
+    pub api: ApiSettings,
+}
+
+/// Where the Claude API key comes from. The key itself stays in the backend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyStatus {
+    /// "environment", "keyring" or "encryptedFile"; `None` without a key
+    pub source: Option<String>,
+    /// Last four characters of the key
+    pub hint: Option<String>,
+}
+
+/// Connection settings of the Claude API client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiSettings {
+    /// API root; messages are posted to `{baseUrl}/v1/messages`
+    pub base_url: String,
+    /// Extra headers sent with every request, e.g. for an API gateway
+    pub headers: HashMap<String, String>,
+    /// HTTP(S) proxy for all requests
+    pub proxy: Option<String>,
+    pub request_timeout_secs: u64,
+    pub connect_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,17 +311,43 @@ pub struct FileCoupling {
     pub file_a: String,
     pub file_b: String,
     pub count: u32,
+    /// Share of all commits that change both files
+    #[serde(default)]
+    pub support: f32,
+    /// Share of commits changing the rarer file that also change the other
+    #[serde(default)]
+    pub confidence: f32,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             api_key: None,
+            provider: "anthropic".to_string(),
+            // llama.cpp's `llama-server` default; Ollama serves on `http://localhost:11434/v1`
+            openai_base_url: "http://localhost:8080/v1".to_string(),
+            openai_model: None,
+            openai_api_key: None,
             claude_model: "claude-sonnet-4-5-20250929".to_string(),
-            max_concurrent_api_calls: 5,
+            max_concurrent_api_calls: 3,
             embedding_batch_size: 32,
             clustering_eps: 0.3,
             clustering_min_samples: 2,
+            max_commits: 2000,
+            models_dir: None,
+            api: ApiSettings::default(),
+        }
+    }
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.anthropic.com".to_string(),
+            headers: HashMap::new(),
+            proxy: None,
+            request_timeout_secs: 120,
+            connect_timeout_secs: 10,
         }
     }
 }
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::RedactionReport;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct FeatureCluster {
     pub cluster_id: i32,
@@ -10,6 +12,8 @@ pub struct FeatureCluster {
     pub narrative: Option<String>,
     pub intent: Option<String>,
     pub key_decisions: Vec<String>,
+    #[serde(default)]
+    pub significant_changes: Vec<SignificantChange>,
     pub commit_hashes: Vec<String>,
     pub time_start: String,
     pub time_end: String,
@@ -20,10 +24,20 @@ pub struct FeatureCluster {
     pub change_type_distribution: HashMap<String, u32>,
     pub dependencies: Vec<i32>,
     pub sub_features: Vec<SubFeature>,
+    #[serde(default)]
+    pub cost_usd: f64,
 }
 
+/// A function change the enrichment model called out as significant.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct SignificantChange {
+    pub function: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct SubFeature {
     pub prompt_text: String,
     pub session_id: String,
@@ -36,25 +50,73 @@ pub struct SubFeature {
     pub lines_removed: u32,
     pub change_type: String,
     pub model: Option<String>,
+    #[serde(default)]
+    pub cost_usd: f64,
+    /// Goal of the prompt this sub-feature came from
+    #[serde(default)]
+    pub intent: Option<String>,
 }
 
+/// What a prompt asked for, as extracted by Claude.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct PromptIntent {
+    pub goal: String,
+    pub requirements: Vec<String>,
+    pub constraints: Vec<String>,
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct PromptSession {
+    /// Logical session id; resumed sessions share the id of the session they continue
     pub session_id: String,
+    /// Session id of the JSONL transcript the prompt was recorded in
+    #[serde(default)]
+    pub transcript_id: String,
     pub prompt_text: String,
     pub timestamp: String,
     pub associated_commit_hashes: Vec<String>,
     pub associated_feature_ids: Vec<i32>,
     pub similarity_score: f32,
     pub scope_match: f32,
+    /// Goal of the prompt; the full extraction is in `intent_details`
     pub intent: Option<String>,
+    #[serde(default)]
+    pub intent_details: Option<PromptIntent>,
     pub files_touched: Vec<String>,
     pub files_written: Vec<String>,
     pub tool_call_count: u32,
     pub model: Option<String>,
     pub token_usage: TokenUsage,
     pub time_end: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub cost_usd: f64,
+    /// Text of the assistant's last response to this prompt
+    #[serde(default)]
+    pub assistant_reply: Option<String>,
+    #[serde(default)]
+    pub tool_errors: Vec<ToolError>,
+    /// The developer interrupted Claude ("[Request interrupted by user]")
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Names of tools whose permission prompt was rejected
+    #[serde(default)]
+    pub permission_denials: Vec<String>,
+    /// API stop reason of each assistant response ("end_turn", "tool_use", "max_tokens", ...)
+    #[serde(default)]
+    pub stop_reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolError {
+    pub tool_name: String,
+    pub tool_use_id: String,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -63,6 +125,8 @@ pub struct TokenUsage {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_read_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_tokens: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +134,8 @@ pub struct TokenUsage {
 pub struct IntentAnalysis {
     pub prompt_text: String,
     pub session_id: String,
+    #[serde(default)]
+    pub timestamp: String,
     pub completion_score: f32,
     pub reprompt_count: u32,
     pub gaps: Vec<String>,
@@ -79,3 +145,11 @@ pub struct IntentAnalysis {
     pub files_actual: Vec<String>,
     pub intent_summary: Option<String>,
 }
+
+/// A repo's prompt sessions from `get_sessions`, redacted like a scan's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsResult {
+    pub sessions: Vec<PromptSession>,
+    pub redaction: RedactionReport,
+}
@@ -1,7 +1,15 @@
+mod bundle;
 mod commit;
 mod enriched;
 mod feature;
+mod project_config;
+mod redaction;
+mod trash;
 
+pub use bundle::*;
 pub use commit::*;
 pub use enriched::*;
 pub use feature::*;
+pub use project_config::*;
+pub use redaction::*;
+pub use trash::*;
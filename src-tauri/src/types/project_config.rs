@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+use super::{RedactionConfig, Subproject};
+
+/// Shared per-repo settings from `.codelens.toml` at the repo root. Keys are
+/// snake_case; unknown keys are errors, so typos don't go unnoticed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Schema version; 1 when omitted
+    pub version: Option<u32>,
+    pub scan: ScanConfig,
+    pub clustering: ClusteringConfig,
+    pub attribution: Option<AttributionConfig>,
+    pub redaction: RedactionConfig,
+    /// Used when `scan_repository` is called without subprojects
+    pub subprojects: Vec<Subproject>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Overrides `AppSettings.max_commits`
+    pub max_commits: Option<u32>,
+    /// Globs of repo-relative files left out of the scan, e.g. `vendor/**`.
+    /// Commits that only touch ignored files are dropped.
+    pub ignore: Vec<String>,
+}
+
+/// Overrides of the `AppSettings` clustering values.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClusteringConfig {
+    pub eps: Option<f32>,
+    pub min_samples: Option<u32>,
+    pub embedding_batch_size: Option<u32>,
+}
+
+/// Which commits count as Claude Code commits. Markers and authors match
+/// case-insensitively anywhere in the commit message or author.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttributionConfig {
+    /// Keep the built-in `Co-Authored-By: Claude` and `Generated by Claude`
+    /// markers and `Claude` authors
+    pub default_rules: bool,
+    /// Commit message markers, e.g. a team's `AI-Assisted: true` trailer
+    pub markers: Vec<String>,
+    /// Author names or emails, e.g. a bot account
+    pub authors: Vec<String>,
+}
+
+impl Default for AttributionConfig {
+    fn default() -> Self {
+        Self {
+            default_rules: true,
+            markers: vec![],
+            authors: vec![],
+        }
+    }
+}
+
+/// The `.codelens.toml` a scan used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectConfigSource {
+    pub path: String,
+    /// SHA-256 of the file, to tell revisions apart
+    pub sha256: String,
+    pub config: ProjectConfig,
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The `[redaction]` table of `.codelens.toml`, applied to session text on
+/// ingest and export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    pub enabled: bool,
+    /// Built-in detector names to skip (e.g. `"email"`, `"ipv4"`)
+    pub disabled_detectors: Vec<String>,
+    /// Team-specific patterns, checked after the built-in detectors
+    pub custom_patterns: Vec<RedactionPattern>,
+    /// Minimum Shannon entropy (bits per char) for the generic high-entropy detector
+    pub min_entropy: f64,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            disabled_detectors: vec![],
+            custom_patterns: vec![],
+            min_entropy: 4.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionPattern {
+    pub name: String,
+    /// Regular expression; if it has a capture group named `secret`, only that
+    /// group is replaced
+    pub pattern: String,
+}
+
+/// Audit of what was redacted while ingesting session logs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionReport {
+    pub total_redactions: u32,
+    pub prompts_affected: u32,
+    pub by_detector: HashMap<String, u32>,
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// Which transcripts `delete_sessions` should act on. Empty filter = all of them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionFilter {
+    /// Logical session ids (a resumed session's transcripts go with it) or transcript ids
+    pub session_ids: Vec<String>,
+    /// Only transcripts whose activity starts on or after this RFC 3339 timestamp or date
+    pub since: Option<String>,
+    /// Only transcripts whose activity ends on or before this RFC 3339 timestamp or date
+    pub until: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionFileInfo {
+    pub path: String,
+    pub session_id: String,
+    pub transcript_id: String,
+    /// Size of the JSONL file plus its session directory, if any
+    pub size_bytes: u64,
+    pub first_timestamp: String,
+    pub last_timestamp: String,
+    pub prompt_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteSessionsResult {
+    pub dry_run: bool,
+    pub files: Vec<SessionFileInfo>,
+    pub total_bytes: u64,
+    /// Trash archive the files were moved to; None for dry runs or when nothing matched
+    pub archive_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSessionsResult {
+    /// Number of files put back
+    pub restored: u32,
+    /// Original paths of files left in the archive because something exists there again
+    pub skipped: Vec<String>,
+}
+
+/// A compressed batch of deleted transcripts that `restore_sessions` can put back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionArchive {
+    pub id: String,
+    pub repo_path: String,
+    pub created_at: String,
+    pub files: Vec<ArchivedFile>,
+    pub total_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedFile {
+    /// Absolute path the file is restored to
+    pub original_path: String,
+    /// Gzip file name inside the archive directory
+    pub stored_as: String,
+    pub size_bytes: u64,
+}
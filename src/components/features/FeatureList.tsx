@@ -1,12 +1,46 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { useFeatures } from "../../hooks/useFeatures";
+import { useProject } from "../../hooks/useProject";
+import { useProjectStore } from "../../store/projectStore";
 import { CHANGE_TYPE_COLORS, CHANGE_TYPE_LABELS } from "../../lib/constants";
-import type { ChangeType, Feature } from "../../lib/types";
+import type { ChangeType, EnrichmentBatch, Feature } from "../../lib/types";
 import { cn, formatNumber } from "../../lib/utils";
 
 export function FeatureList() {
   const { features } = useFeatures();
+  const {
+    enrichFeatures,
+    submitEnrichmentBatch,
+    watchEnrichmentBatches,
+    extractIntents,
+    streamNarrative,
+  } = useProject();
+  const isScanning = useProjectStore((s) => s.isScanning);
+  const projectId = useProjectStore((s) => s.activeProject?.id);
   const [expandedId, setExpandedId] = useState<number | null>(null);
+  const [batches, setBatches] = useState<EnrichmentBatch[]>([]);
+
+  useEffect(() => {
+    if (!projectId) return;
+    invoke<EnrichmentBatch[]>("list_enrichment_batches", { projectId })
+      .then(setBatches)
+      .catch((err) => console.error("Failed to list batches:", err));
+    const unlisten = watchEnrichmentBatches((batch) => {
+      if (batch.projectId !== projectId) return;
+      setBatches((current) => [
+        batch,
+        ...current.filter((b) => b.batchId !== batch.batchId),
+      ]);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [projectId, watchEnrichmentBatches]);
+
+  const pendingFeatures = batches
+    .filter((b) => b.status !== "applied")
+    .reduce((sum, b) => sum + b.items.length, 0);
 
   if (features.length === 0) {
     return (
@@ -23,7 +57,36 @@ export function FeatureList() {
       <div className="flex items-center justify-between border-b border-[var(--color-border)] px-4 py-2">
         <span className="text-xs text-[var(--color-text-tertiary)]">
           {features.length} features
+          {pendingFeatures > 0 &&
+            ` · ${pendingFeatures} enriching in batch`}
         </span>
+        <div className="flex items-center gap-2">
+          <button
+            onClick={() => extractIntents()}
+            disabled={isScanning}
+            className="rounded-md border border-[var(--color-border)] px-2 py-1 text-[11px] text-[var(--color-text-secondary)] hover:border-[var(--color-border-focus)] disabled:opacity-50"
+          >
+            Extract intents
+          </button>
+          <button
+            onClick={() => enrichFeatures()}
+            disabled={isScanning}
+            className="rounded-md border border-[var(--color-border)] px-2 py-1 text-[11px] text-[var(--color-text-secondary)] hover:border-[var(--color-border-focus)] disabled:opacity-50"
+          >
+            Enrich with Claude
+          </button>
+          <button
+            onClick={async () => {
+              const submitted = await submitEnrichmentBatch();
+              setBatches((current) => [...submitted, ...current]);
+            }}
+            disabled={isScanning || pendingFeatures > 0}
+            title="Half price; results usually arrive within an hour"
+            className="rounded-md border border-[var(--color-border)] px-2 py-1 text-[11px] text-[var(--color-text-secondary)] hover:border-[var(--color-border-focus)] disabled:opacity-50"
+          >
+            Enrich in batch
+          </button>
+        </div>
       </div>
 
       <div className="flex-1 overflow-auto p-4 space-y-2">
@@ -37,6 +100,8 @@ export function FeatureList() {
                 expandedId === feature.clusterId ? null : feature.clusterId
               )
             }
+            onWriteNarrative={() => streamNarrative(feature.clusterId)}
+            disabled={isScanning}
           />
         ))}
       </div>
@@ -48,10 +113,14 @@ function FeatureCard({
   feature,
   isExpanded,
   onToggle,
+  onWriteNarrative,
+  disabled,
 }: {
   feature: Feature;
   isExpanded: boolean;
   onToggle: () => void;
+  onWriteNarrative: () => void;
+  disabled: boolean;
 }) {
   const dominantType = Object.entries(feature.changeTypeDistribution).sort(
     ([, a], [, b]) => b - a
@@ -83,7 +152,12 @@ function FeatureCard({
               )}
             </div>
             {feature.narrative && (
-              <p className="mt-1 text-xs text-[var(--color-text-tertiary)] line-clamp-2">
+              <p
+                className={cn(
+                  "mt-1 text-xs text-[var(--color-text-tertiary)] whitespace-pre-line",
+                  !isExpanded && "line-clamp-2"
+                )}
+              >
                 {feature.narrative}
               </p>
             )}
@@ -120,6 +194,18 @@ function FeatureCard({
         </div>
       </button>
 
+      {isExpanded && (
+        <div className="flex justify-end px-4 pb-2">
+          <button
+            onClick={onWriteNarrative}
+            disabled={disabled}
+            className="rounded-md border border-[var(--color-border)] px-2 py-1 text-[11px] text-[var(--color-text-secondary)] hover:border-[var(--color-border-focus)] disabled:opacity-50"
+          >
+            {feature.narrative ? "Rewrite narrative" : "Write narrative"}
+          </button>
+        </div>
+      )}
+
       {/* Sub-features (expanded) */}
       {isExpanded && hasSubFeatures && (
         <div className="border-t border-[var(--color-border)] px-4 py-3">
@@ -22,7 +22,12 @@ export function IntentDashboard() {
 
   if (!activeProject) return null;
 
-  const { promptSessions } = activeProject;
+  const { promptSessions, intentAnalyses = [] } = activeProject;
+
+  // Backend analyses (completion, re-prompts, rework) keyed by prompt
+  const analysisByPrompt = new Map(
+    intentAnalyses.map((a) => [`${a.sessionId}|${a.timestamp}`, a])
+  );
 
   // Compute intent metrics from prompt sessions
   const totalPrompts = promptSessions.length;
@@ -36,13 +41,24 @@ export function IntentDashboard() {
       : 0;
 
   // Detect re-prompts (simplified: consecutive prompts in same session with similar text)
-  const repromptCount = detectReprompts(promptSessions);
+  const repromptCount =
+    intentAnalyses.length > 0
+      ? intentAnalyses.reduce((sum, a) => sum + a.repromptCount, 0)
+      : detectReprompts(promptSessions);
 
   // Classify outcomes heuristically
   const outcomes = promptSessions.map((session) => {
+    const analysis = analysisByPrompt.get(
+      `${session.sessionId}|${session.timestamp}`
+    );
+    if (analysis) return analysis.outcome;
+
     const hasWrites = session.filesWritten.length > 0;
     const hasTools = session.toolCallCount > 0;
+    const stoppedEarly =
+      session.interrupted || (session.permissionDenials?.length ?? 0) > 0;
 
+    if (stoppedEarly) return hasWrites ? "partial" : "abandoned";
     if (hasWrites) return "completed";
     if (hasTools) return "partial";
     return "abandoned";
@@ -200,8 +216,37 @@ export function IntentDashboard() {
                       )}
                     </div>
                   </div>
+                  {(session.toolErrors?.length ?? 0) > 0 && (
+                    <div>
+                      <p className="text-[10px] text-[var(--color-text-muted)] mb-1">
+                        Tool Errors
+                      </p>
+                      {session.toolErrors!.map((err) => (
+                        <p
+                          key={err.toolUseId}
+                          className="text-xs font-mono text-[#f87171] truncate"
+                        >
+                          {err.toolName}: {err.message}
+                        </p>
+                      ))}
+                    </div>
+                  )}
+                  {session.assistantReply && (
+                    <div>
+                      <p className="text-[10px] text-[var(--color-text-muted)] mb-1">
+                        Final Reply
+                      </p>
+                      <p className="text-xs text-[var(--color-text-tertiary)] line-clamp-3">
+                        {session.assistantReply}
+                      </p>
+                    </div>
+                  )}
                   <div className="flex gap-4 text-[10px] text-[var(--color-text-muted)]">
                     <span>Session: {session.sessionId.slice(0, 8)}...</span>
+                    {session.interrupted && <span>Interrupted</span>}
+                    {(session.permissionDenials?.length ?? 0) > 0 && (
+                      <span>Denied: {session.permissionDenials!.join(", ")}</span>
+                    )}
                     {session.model && <span>Model: {session.model}</span>}
                     <span>
                       Tokens: {session.tokenUsage.inputTokens.toLocaleString()} in /{" "}
@@ -1,20 +1,33 @@
+import { useEffect } from "react";
 import { useProjectStore } from "../../store/projectStore";
+import { useTauriCommand } from "../../hooks/useTauriCommand";
 import { ProfileCard } from "./ProfileCard";
-import type { Commit } from "../../lib/types";
+import type { Commit, DeveloperProfile } from "../../lib/types";
 
 export function PatternDashboard() {
   const activeProject = useProjectStore((s) => s.activeProject);
+  const { data: profile, execute: loadProfile } =
+    useTauriCommand<DeveloperProfile>("get_developer_profile");
+
+  const projectId = activeProject?.id;
+  useEffect(() => {
+    if (projectId) loadProfile({ projectId });
+  }, [projectId, loadProfile]);
 
   if (!activeProject) return null;
 
   const { commits, analytics, promptSessions } = activeProject;
 
-  // Compute patterns from available data
+  // Compute patterns from available data, preferring the backend profile
   const hourCounts = computeHourDistribution(commits);
   const dayCounts = computeDayDistribution(commits);
-  const fileCouplings = computeFileCouplings(commits);
-  const peakHours = findPeakHours(hourCounts, 3);
-  const avgGranularity = computeAvgGranularity(commits);
+  const fileCouplings: [string, string, number][] = profile
+    ? profile.fileCouplings.map((c) => [c.fileA, c.fileB, c.count])
+    : computeFileCouplings(commits);
+  const peakHours = profile?.peakHours ?? findPeakHours(hourCounts, 3);
+  const avgGranularity = profile
+    ? Math.round(profile.avgCommitGranularity * 10) / 10
+    : computeAvgGranularity(commits);
 
   const dayLabels = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
   const maxHourCount = Math.max(...hourCounts, 1);
@@ -28,7 +41,7 @@ export function PatternDashboard() {
         peakHours={peakHours}
         avgGranularity={avgGranularity}
         totalCommits={commits.length}
-        totalSessions={promptSessions.length}
+        totalSessions={profile?.totalSessions ?? promptSessions.length}
         claudePercentage={analytics.claudeCodeCommitPercentage}
       />
 
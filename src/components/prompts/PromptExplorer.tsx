@@ -2,7 +2,7 @@ import { useMemo, useState } from "react";
 import { useProjectStore } from "../../store/projectStore";
 import { useProject } from "../../hooks/useProject";
 import { cn } from "../../lib/utils";
-import type { PromptSession } from "../../lib/types";
+import type { DeleteSessionsResult, PromptSession } from "../../lib/types";
 
 type GroupBy = "hour" | "day" | "week" | "month";
 
@@ -56,15 +56,30 @@ function groupSessions(
   return Array.from(groups.entries());
 }
 
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
 export function PromptExplorer() {
   const activeProject = useProjectStore((s) => s.activeProject);
-  const { refreshSessions, deleteSessions } = useProject();
+  const {
+    refreshSessions,
+    previewDeleteSessions,
+    deleteSessions,
+    restoreSessions,
+    exportSessions,
+    importSessions,
+  } = useProject();
   const sessions = activeProject?.promptSessions ?? [];
 
   const [groupBy, setGroupBy] = useState<GroupBy>("day");
   const [cleared, setCleared] = useState(false);
   const [expandedId, setExpandedId] = useState<string | null>(null);
   const [showDeleteConfirm, setShowDeleteConfirm] = useState(false);
+  const [deletePreview, setDeletePreview] = useState<DeleteSessionsResult | null>(null);
+  const [lastArchiveId, setLastArchiveId] = useState<string | null>(null);
   const [refreshing, setRefreshing] = useState(false);
 
   const grouped = useMemo(
@@ -79,9 +94,21 @@ export function PromptExplorer() {
     setCleared(false);
   };
 
+  const handleDeleteRequest = async () => {
+    setDeletePreview(await previewDeleteSessions());
+    setShowDeleteConfirm(true);
+  };
+
   const handleDeleteAll = async () => {
-    await deleteSessions();
+    const result = await deleteSessions();
     setShowDeleteConfirm(false);
+    setLastArchiveId(result?.archiveId ?? null);
+  };
+
+  const handleUndoDelete = async () => {
+    if (!lastArchiveId) return;
+    await restoreSessions(lastArchiveId);
+    setLastArchiveId(null);
   };
 
   if (sessions.length === 0 && !cleared) {
@@ -148,6 +175,14 @@ export function PromptExplorer() {
             {refreshing ? "..." : "Refresh"}
           </button>
 
+          {/* Import a session bundle */}
+          <button
+            onClick={() => importSessions()}
+            className="rounded px-2 py-1 text-[11px] text-[var(--color-text-muted)] hover:text-[var(--color-text-secondary)] hover:bg-[var(--color-surface-2)] transition-colors"
+          >
+            Import
+          </button>
+
           {/* Clear temp */}
           <button
             onClick={() => setCleared((c) => !c)}
@@ -158,7 +193,7 @@ export function PromptExplorer() {
 
           {/* Delete all */}
           <button
-            onClick={() => setShowDeleteConfirm(true)}
+            onClick={handleDeleteRequest}
             className="rounded px-2 py-1 text-[11px] text-red-400/70 hover:text-red-400 hover:bg-red-400/10 transition-colors"
           >
             Delete All
@@ -170,8 +205,10 @@ export function PromptExplorer() {
       {showDeleteConfirm && (
         <div className="flex items-center justify-between border-b border-red-500/20 bg-red-500/5 px-4 py-2">
           <span className="text-xs text-red-400">
-            This will permanently delete all session JSONL files for this
-            project. This cannot be undone.
+            {deletePreview
+              ? `Move ${deletePreview.files.length} session files (${formatBytes(deletePreview.totalBytes)}) to the CodeLens trash.`
+              : "Move all session JSONL files for this project to the CodeLens trash."}{" "}
+            Prompts stay in CodeLens history and the files can be restored.
           </span>
           <div className="flex gap-2">
             <button
@@ -190,6 +227,21 @@ export function PromptExplorer() {
         </div>
       )}
 
+      {/* Undo last delete */}
+      {lastArchiveId && !showDeleteConfirm && (
+        <div className="flex items-center justify-between border-b border-[var(--color-border)] px-4 py-2">
+          <span className="text-xs text-[var(--color-text-muted)]">
+            Session files moved to trash.
+          </span>
+          <button
+            onClick={handleUndoDelete}
+            className="rounded px-3 py-1 text-[11px] text-[var(--color-text-secondary)] hover:bg-[var(--color-surface-2)] transition-colors"
+          >
+            Undo
+          </button>
+        </div>
+      )}
+
       {/* Content */}
       {cleared ? (
         <div className="flex flex-1 items-center justify-center">
@@ -298,6 +350,16 @@ export function PromptExplorer() {
 
                       {isExpanded && (
                         <div className="border-t border-[var(--color-border)] px-4 py-3 space-y-3">
+                          <div className="flex justify-end">
+                            <button
+                              onClick={() =>
+                                exportSessions({ sessionIds: [session.sessionId] })
+                              }
+                              className="rounded px-2 py-0.5 text-[11px] text-[var(--color-text-muted)] hover:text-[var(--color-text-secondary)] hover:bg-[var(--color-surface-2)] transition-colors"
+                            >
+                              Export session
+                            </button>
+                          </div>
                           {session.filesWritten.length > 0 && (
                             <div>
                               <h4 className="text-[11px] font-medium text-[var(--color-text-secondary)] mb-1">
@@ -1,7 +1,22 @@
 import { useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useProjectStore } from "../store/projectStore";
-import type { Project, ProjectData, PromptSession } from "../lib/types";
+import type {
+  BundleManifest,
+  DeleteSessionsResult,
+  EnrichmentBatch,
+  EnrichmentProgress,
+  Feature,
+  ImportSessionsResult,
+  IntentProgress,
+  NarrativeDelta,
+  Project,
+  ProjectData,
+  RestoreSessionsResult,
+  SessionFilter,
+  SessionsResult,
+} from "../lib/types";
 
 export function useProject() {
   const {
@@ -51,15 +66,171 @@ export function useProject() {
     [setIsScanning, setScanProgress, setActiveProject]
   );
 
+  const enrichFeatures = useCallback(
+    async (options?: { featureIds?: number[]; force?: boolean }) => {
+      if (!activeProject?.id) return;
+      setIsScanning(true);
+      setScanProgress(0, "Enriching features...");
+      const unlisten = await listen<EnrichmentProgress>(
+        "enrichment-progress",
+        ({ payload }) => {
+          setScanProgress(
+            payload.completed / payload.total,
+            `Enriched ${payload.completed}/${payload.total} features` +
+              (payload.cacheSavingsUsd > 0
+                ? ` (prompt cache saved $${payload.cacheSavingsUsd.toFixed(2)})`
+                : "")
+          );
+        }
+      );
+      try {
+        const data = await invoke<ProjectData>("enrich_features", {
+          projectId: activeProject.id,
+          featureIds: options?.featureIds,
+          force: options?.force,
+        });
+        setActiveProject(data);
+      } catch (err) {
+        console.error("Enrichment failed:", err);
+      } finally {
+        unlisten();
+        setIsScanning(false);
+      }
+    },
+    [activeProject, setIsScanning, setScanProgress, setActiveProject]
+  );
+
+  const submitEnrichmentBatch = useCallback(
+    async (options?: { featureIds?: number[]; force?: boolean }) => {
+      if (!activeProject?.id) return [];
+      try {
+        const batches = await invoke<EnrichmentBatch[]>(
+          "submit_enrichment_batch",
+          {
+            projectId: activeProject.id,
+            featureIds: options?.featureIds,
+            force: options?.force,
+          }
+        );
+        // Cached enrichments were applied before submitting the rest
+        const data = await invoke<ProjectData>("get_project_data", {
+          projectId: activeProject.id,
+        });
+        setActiveProject(data);
+        return batches;
+      } catch (err) {
+        console.error("Batch enrichment failed:", err);
+        return [];
+      }
+    },
+    [activeProject, setActiveProject]
+  );
+
+  const watchEnrichmentBatches = useCallback(
+    (onBatch: (batch: EnrichmentBatch) => void) =>
+      listen<EnrichmentBatch>("enrichment-batch", async ({ payload }) => {
+        onBatch(payload);
+        const current = useProjectStore.getState().activeProject;
+        if (payload.status !== "applied" || current?.id !== payload.projectId) {
+          return;
+        }
+        try {
+          const data = await invoke<ProjectData>("get_project_data", {
+            projectId: payload.projectId,
+          });
+          setActiveProject(data);
+        } catch (err) {
+          console.error("Failed to reload enriched project:", err);
+        }
+      }),
+    [setActiveProject]
+  );
+
+  const streamNarrative = useCallback(
+    async (featureId: number) => {
+      if (!activeProject?.id) return;
+      const updateFeature = (update: (feature: Feature) => Feature) => {
+        const current = useProjectStore.getState().activeProject;
+        if (!current) return;
+        setActiveProject({
+          ...current,
+          features: current.features.map((f) =>
+            f.clusterId === featureId ? update(f) : f
+          ),
+        });
+      };
+
+      // Render the narrative as it streams in, replacing the old one
+      const previous = activeProject.features.find(
+        (f) => f.clusterId === featureId
+      )?.narrative;
+      updateFeature((f) => ({ ...f, narrative: "" }));
+      const unlisten = await listen<NarrativeDelta>(
+        "narrative-delta",
+        ({ payload }) => {
+          if (payload.clusterId !== featureId) return;
+          updateFeature((f) => ({
+            ...f,
+            narrative: (f.narrative ?? "") + payload.text,
+          }));
+        }
+      );
+      try {
+        const feature = await invoke<Feature>("stream_feature_narrative", {
+          projectId: activeProject.id,
+          featureId,
+        });
+        updateFeature(() => feature);
+      } catch (err) {
+        console.error("Narrative streaming failed:", err);
+        updateFeature((f) => ({ ...f, narrative: previous ?? null }));
+      } finally {
+        unlisten();
+      }
+    },
+    [activeProject, setActiveProject]
+  );
+
+  const extractIntents = useCallback(
+    async (options?: { force?: boolean }) => {
+      if (!activeProject?.id) return;
+      setIsScanning(true);
+      setScanProgress(0, "Extracting prompt intents...");
+      const unlisten = await listen<IntentProgress>(
+        "intent-progress",
+        ({ payload }) => {
+          setScanProgress(
+            payload.completed / payload.total,
+            `Extracted ${payload.completed}/${payload.total} prompt intents`
+          );
+        }
+      );
+      try {
+        const data = await invoke<ProjectData>("extract_prompt_intents", {
+          projectId: activeProject.id,
+          force: options?.force,
+        });
+        setActiveProject(data);
+      } catch (err) {
+        console.error("Intent extraction failed:", err);
+      } finally {
+        unlisten();
+        setIsScanning(false);
+      }
+    },
+    [activeProject, setIsScanning, setScanProgress, setActiveProject]
+  );
+
   const refreshSessions = useCallback(async () => {
     if (!activeProject) return;
     try {
-      const sessions = await invoke<PromptSession[]>("get_sessions", {
+      const { sessions, redaction } = await invoke<SessionsResult>("get_sessions", {
         path: activeProject.repository.path,
       });
       setActiveProject({
         ...activeProject,
         promptSessions: sessions,
+        redaction,
         analytics: {
           ...activeProject.analytics,
           totalPromptsDetected: sessions.length,
@@ -70,31 +241,121 @@ export function useProject() {
     }
   }, [activeProject, setActiveProject]);
 
-  const deleteSessions = useCallback(async () => {
-    if (!activeProject) return;
+  const previewDeleteSessions = useCallback(
+    async (filter?: SessionFilter) => {
+      if (!activeProject) return null;
+      try {
+        return await invoke<DeleteSessionsResult>("delete_sessions", {
+          path: activeProject.repository.path,
+          filter,
+          dryRun: true,
+        });
+      } catch (err) {
+        console.error("Failed to preview session deletion:", err);
+        return null;
+      }
+    },
+    [activeProject]
+  );
+
+  // Session files are moved to the CodeLens trash; their prompts stay in history
+  const deleteSessions = useCallback(
+    async (filter?: SessionFilter) => {
+      if (!activeProject) return null;
+      try {
+        const result = await invoke<DeleteSessionsResult>("delete_sessions", {
+          path: activeProject.repository.path,
+          filter,
+        });
+        console.log(
+          `Moved ${result.files.length} session files to trash ${result.archiveId}`
+        );
+        return result;
+      } catch (err) {
+        console.error("Failed to delete sessions:", err);
+        return null;
+      }
+    },
+    [activeProject]
+  );
+
+  const restoreSessions = useCallback(
+    async (archiveId: string) => {
+      try {
+        const result = await invoke<RestoreSessionsResult>("restore_sessions", { archiveId });
+        console.log(`Restored ${result.restored} session files`);
+        if (result.skipped.length > 0) {
+          console.warn(
+            `Kept ${result.skipped.length} files in the trash because they exist again:`,
+            result.skipped
+          );
+        }
+        await refreshSessions();
+      } catch (err) {
+        console.error("Failed to restore sessions:", err);
+      }
+    },
+    [refreshSessions]
+  );
+
+  const exportSessions = useCallback(
+    async (selection: { sessionIds?: string[]; featureIds?: number[] }) => {
+      if (!activeProject) return null;
+      try {
+        const { save } = await import("@tauri-apps/plugin-dialog");
+        const outputPath = await save({
+          defaultPath: `${activeProject.repository.name}-sessions.codelens.gz`,
+        });
+        if (!outputPath) return null;
+        return await invoke<BundleManifest>("export_sessions", {
+          project: activeProject,
+          outputPath,
+          sessionIds: selection.sessionIds,
+          featureIds: selection.featureIds,
+        });
+      } catch (err) {
+        console.error("Failed to export sessions:", err);
+        return null;
+      }
+    },
+    [activeProject]
+  );
+
+  const importSessions = useCallback(async () => {
+    if (!activeProject) return null;
     try {
-      const deleted = await invoke<number>("delete_sessions", {
+      const { open } = await import("@tauri-apps/plugin-dialog");
+      const bundlePath = await open({ multiple: false });
+      if (!bundlePath) return null;
+      const result = await invoke<ImportSessionsResult>("import_sessions", {
         path: activeProject.repository.path,
+        bundlePath,
       });
-      console.log(`Deleted ${deleted} session files`);
-      setActiveProject({
-        ...activeProject,
-        promptSessions: [],
-        analytics: {
-          ...activeProject.analytics,
-          totalPromptsDetected: 0,
-        },
-      });
+      console.log(
+        `Imported ${result.importedPrompts} prompts from ${result.manifest.repository}`
+      );
+      await refreshSessions();
+      return result;
     } catch (err) {
-      console.error("Failed to delete sessions:", err);
+      console.error("Failed to import sessions:", err);
+      return null;
     }
-  }, [activeProject, setActiveProject]);
+  }, [activeProject, refreshSessions]);
 
   return {
     loadProjects,
     openProject,
     scanRepository,
+    enrichFeatures,
+    submitEnrichmentBatch,
+    watchEnrichmentBatches,
+    streamNarrative,
+    extractIntents,
     refreshSessions,
+    previewDeleteSessions,
     deleteSessions,
+    restoreSessions,
+    exportSessions,
+    importSessions,
   };
 }
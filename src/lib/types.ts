@@ -54,6 +54,7 @@ export interface Feature {
   narrative: string | null;
   intent: string | null;
   keyDecisions: string[];
+  significantChanges?: SignificantChange[];
   commitHashes: string[];
   timeStart: string;
   timeEnd: string;
@@ -64,6 +65,12 @@ export interface Feature {
   changeTypeDistribution: Record<ChangeType, number>;
   dependencies: number[];
   subFeatures: SubFeature[];
+  costUsd?: number;
+}
+
+export interface SignificantChange {
+  function: string;
+  description: string;
 }
 
 export interface SubFeature {
@@ -78,10 +85,20 @@ export interface SubFeature {
   linesRemoved: number;
   changeType: ChangeType;
   model: string | null;
+  costUsd?: number;
+  intent?: string | null;
+}
+
+export interface PromptIntent {
+  goal: string;
+  requirements: string[];
+  constraints: string[];
+  context: string;
 }
 
 export interface PromptSession {
   sessionId: string;
+  transcriptId?: string;
   promptText: string;
   timestamp: string;
   associatedCommitHashes: string[];
@@ -89,18 +106,33 @@ export interface PromptSession {
   similarityScore: number;
   scopeMatch: number;
   intent: string | null;
+  intentDetails?: PromptIntent | null;
   filesTouched: string[];
   filesWritten: string[];
   toolCallCount: number;
   model: string | null;
   tokenUsage: TokenUsage;
   timeEnd: string | null;
+  cwd?: string | null;
+  costUsd?: number;
+  assistantReply?: string | null;
+  toolErrors?: ToolError[];
+  interrupted?: boolean;
+  permissionDenials?: string[];
+  stopReasons?: string[];
+}
+
+export interface ToolError {
+  toolName: string;
+  toolUseId: string;
+  message: string;
 }
 
 export interface TokenUsage {
   inputTokens: number;
   outputTokens: number;
   cacheReadTokens: number;
+  cacheCreationTokens?: number;
 }
 
 export interface Analytics {
@@ -112,15 +144,21 @@ export interface Analytics {
   mostModifiedFiles: string[];
   mostModifiedFunctions: string[];
   changeTypeTotals: Record<ChangeType, number>;
-  velocityByWeek: { week: string; features: number; commits: number }[];
+  velocityByWeek: { week: string; features: number; commits: number; costUsd?: number }[];
   // Phase 7: Extended analytics
   avgIntentCompletion?: number;
   repromptRate?: number;
   patternCount?: number;
   embeddingCoverage?: number;
+  // Cost accounting
+  totalCostUsd?: number;
+  avgCostPerFeature?: number;
+  pricingVersion?: string;
 }
 
 export interface ProjectData {
+  /** Id of the saved project; absent for subproject views */
+  id?: string;
   repository: {
     path: string;
     name: string;
@@ -132,12 +170,138 @@ export interface ProjectData {
   features: Feature[];
   promptSessions: PromptSession[];
   analytics: Analytics;
+  subprojects?: SubprojectSummary[];
+  redaction?: RedactionReport;
+  intentAnalyses?: IntentAnalysis[];
   developerProfile?: DeveloperProfile | null;
+  /** The repo's .codelens.toml, when the scan used one */
+  projectConfig?: ProjectConfigSource | null;
+  /** The Mojo engine that produced the scan; null for the Rust fallback */
+  engine?: EngineInfo | null;
+}
+
+export interface Subproject {
+  name: string;
+  paths: string[];
+}
+
+/** Parsed .codelens.toml; keys keep the file's snake_case */
+export interface ProjectConfig {
+  version?: number | null;
+  scan: { max_commits?: number | null; ignore: string[] };
+  clustering: {
+    eps?: number | null;
+    min_samples?: number | null;
+    embedding_batch_size?: number | null;
+  };
+  attribution?: { default_rules: boolean; markers: string[]; authors: string[] } | null;
+  redaction: RedactionConfig;
+  subprojects: Subproject[];
+}
+
+export interface ProjectConfigSource {
+  path: string;
+  sha256: string;
+  config: ProjectConfig;
+}
+
+export interface SubprojectSummary extends Subproject {
+  commitHashes: string[];
+  featureIds: number[];
+  totalPrompts: number;
+  analytics: Analytics;
+}
+
+export interface SessionFilter {
+  sessionIds?: string[];
+  since?: string;
+  until?: string;
+}
+
+export interface SessionFileInfo {
+  path: string;
+  sessionId: string;
+  transcriptId: string;
+  sizeBytes: number;
+  firstTimestamp: string;
+  lastTimestamp: string;
+  promptCount: number;
+}
+
+export interface DeleteSessionsResult {
+  dryRun: boolean;
+  files: SessionFileInfo[];
+  totalBytes: number;
+  archiveId: string | null;
+}
+
+export interface RestoreSessionsResult {
+  restored: number;
+  /** Original paths left in the archive because a file exists there again */
+  skipped: string[];
+}
+
+export interface ArchivedFile {
+  originalPath: string;
+  storedAs: string;
+  sizeBytes: number;
+}
+
+export interface SessionArchive {
+  id: string;
+  repoPath: string;
+  createdAt: string;
+  files: ArchivedFile[];
+  totalBytes: number;
+  compressedBytes: number;
+}
+
+export interface BundleManifest {
+  formatVersion: number;
+  bundleId: string;
+  createdAt: string;
+  repository: string;
+  sessionIds: string[];
+  featureIds: number[];
+  transcriptCount: number;
+  promptCount: number;
+  redacted: boolean;
+  redactionCounts: Record<string, number>;
+}
+
+export interface ImportSessionsResult {
+  manifest: BundleManifest;
+  importedPrompts: number;
+}
+
+export interface RedactionPattern {
+  name: string;
+  pattern: string;
+}
+
+/** The [redaction] table of .codelens.toml */
+export interface RedactionConfig {
+  enabled: boolean;
+  disabled_detectors: string[];
+  custom_patterns: RedactionPattern[];
+  min_entropy: number;
+}
+
+export interface RedactionReport {
+  totalRedactions: number;
+  promptsAffected: number;
+  byDetector: Record<string, number>;
+}
+
+export interface SessionsResult {
+  sessions: PromptSession[];
+  redaction: RedactionReport;
 }
 
 export interface IntentAnalysis {
   promptText: string;
   sessionId: string;
+  timestamp?: string;
   completionScore: number;
   repromptCount: number;
   gaps: string[];
@@ -156,17 +320,106 @@ export interface DeveloperProfile {
   commonChangeTypes: Record<ChangeType, number>;
   peakHours: number[];
   avgCommitGranularity: number;
-  fileCouplings: Array<{ fileA: string; fileB: string; count: number }>;
+  fileCouplings: FileCoupling[];
   totalSessions: number;
   totalPrompts: number;
 }
 
+export interface FileCoupling {
+  fileA: string;
+  fileB: string;
+  count: number;
+  support: number;
+  confidence: number;
+}
+
 export interface ScanProgress {
   stage: string;
   progress: number;
   message: string;
 }
 
+/** What the Mojo engine announced: agreed protocol, version, capabilities */
+export interface EngineInfo {
+  protocolVersion: number;
+  engineVersion: string;
+  capabilities: string[];
+}
+
+export interface IntentProgress {
+  sessionId: string;
+  timestamp: string;
+  status: "extracted" | "failed";
+  goal: string | null;
+  error: string | null;
+  completed: number;
+  total: number;
+}
+
+export interface NarrativeDelta {
+  clusterId: number;
+  text: string;
+}
+
+export interface EnrichmentProgress {
+  clusterId: number;
+  status: "enriched" | "failed";
+  title: string | null;
+  error: string | null;
+  completed: number;
+  total: number;
+  usage: TokenUsage;
+  cacheSavingsUsd: number;
+}
+
+export interface EnrichmentBatchItem {
+  customId: string;
+  clusterId: number;
+  cacheKey: string;
+}
+
+export interface EnrichmentBatch {
+  batchId: string;
+  projectId: string;
+  model: string;
+  status: "in_progress" | "canceling" | "ended" | "applied";
+  createdAt: string;
+  items: EnrichmentBatchItem[];
+  processing: number;
+  succeeded: number;
+  errored: number;
+  expired: number;
+  applied: number;
+  error: string | null;
+}
+
+export interface ApiSettings {
+  baseUrl: string;
+  headers: Record<string, string>;
+  proxy: string | null;
+  requestTimeoutSecs: number;
+  connectTimeoutSecs: number;
+}
+
+export interface ApiKeyStatus {
+  source: "environment" | "keyring" | "encryptedFile" | null;
+  hint: string | null;
+}
+
+export interface AppSettings {
+  provider: "anthropic" | "openai";
+  openaiBaseUrl: string;
+  openaiModel: string | null;
+  claudeModel: string;
+  maxConcurrentApiCalls: number;
+  embeddingBatchSize: number;
+  clusteringEps: number;
+  clusteringMinSamples: number;
+  maxCommits: number;
+  modelsDir: string | null;
+  api: ApiSettings;
+}
+
 export interface FeatureNodeData {
   clusterId: number;
   title: string;
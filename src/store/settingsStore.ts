@@ -1,23 +1,71 @@
+import { invoke } from "@tauri-apps/api/core";
 import { create } from "zustand";
+import type { ApiKeyStatus, AppSettings } from "../lib/types";
 
 interface SettingsState {
-  apiKey: string;
-  claudeModel: string;
-  maxConcurrentApiCalls: number;
+  settings: AppSettings | null;
+  apiKeyStatus: ApiKeyStatus | null;
+  error: string | null;
   showSettings: boolean;
-  setApiKey: (key: string) => void;
-  setClaudeModel: (model: string) => void;
-  setMaxConcurrentApiCalls: (n: number) => void;
+  loadSettings: () => Promise<void>;
+  saveSettings: (settings: AppSettings) => Promise<void>;
+  setApiKey: (key: string) => Promise<void>;
+  clearApiKey: () => Promise<void>;
+  testApiKey: (key?: string) => Promise<boolean>;
   toggleSettings: () => void;
 }
 
 export const useSettingsStore = create<SettingsState>((set) => ({
-  apiKey: "",
-  claudeModel: "claude-sonnet-4-5-20250929",
-  maxConcurrentApiCalls: 5,
+  settings: null,
+  apiKeyStatus: null,
+  error: null,
   showSettings: false,
-  setApiKey: (key) => set({ apiKey: key }),
-  setClaudeModel: (model) => set({ claudeModel: model }),
-  setMaxConcurrentApiCalls: (n) => set({ maxConcurrentApiCalls: n }),
+  loadSettings: async () => {
+    try {
+      const [settings, apiKeyStatus] = await Promise.all([
+        invoke<AppSettings>("get_settings"),
+        invoke<ApiKeyStatus>("get_api_key_status"),
+      ]);
+      set({ settings, apiKeyStatus, error: null });
+    } catch (e) {
+      set({ error: String(e) });
+    }
+  },
+  saveSettings: async (settings) => {
+    try {
+      await invoke("update_settings", { settings });
+      set({ settings, error: null });
+    } catch (e) {
+      // Validation errors list every invalid field
+      set({ error: String(e) });
+    }
+  },
+  // The key is only ever sent to the backend; status carries its last characters
+  setApiKey: async (key) => {
+    try {
+      const apiKeyStatus = await invoke<ApiKeyStatus>("set_api_key", { key });
+      set({ apiKeyStatus, error: null });
+    } catch (e) {
+      set({ error: String(e) });
+    }
+  },
+  clearApiKey: async () => {
+    try {
+      const apiKeyStatus = await invoke<ApiKeyStatus>("clear_api_key");
+      set({ apiKeyStatus, error: null });
+    } catch (e) {
+      set({ error: String(e) });
+    }
+  },
+  testApiKey: async (key) => {
+    try {
+      await invoke("test_api_key", { key: key ?? null });
+      set({ error: null });
+      return true;
+    } catch (e) {
+      set({ error: String(e) });
+      return false;
+    }
+  },
   toggleSettings: () => set((s) => ({ showSettings: !s.showSettings })),
 }));